@@ -0,0 +1,98 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Index, parse_macro_input};
+
+/// Implements `ph_map::PhKey` for a fieldless enum, numbering variants in declaration order.
+#[proc_macro_derive(PhKey)]
+pub fn derive_ph_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Enum(data) = &input.data else {
+        return syn::Error::new_spanned(&input.ident, "`PhKey` can only be derived for enums")
+            .to_compile_error()
+            .into();
+    };
+
+    if let Some(variant) = data
+        .variants
+        .iter()
+        .find(|variant| !matches!(variant.fields, Fields::Unit))
+    {
+        return syn::Error::new_spanned(variant, "`PhKey` variants must not have fields")
+            .to_compile_error()
+            .into();
+    }
+
+    let variants = data.variants.iter().map(|variant| &variant.ident);
+    let indices = 0..data.variants.len();
+    let count = data.variants.len();
+
+    let to_index = variants
+        .clone()
+        .zip(indices.clone())
+        .map(|(variant, index)| quote!(Self::#variant => #index));
+    let from_index = variants
+        .zip(indices)
+        .map(|(variant, index)| quote!(#index => ::core::option::Option::Some(Self::#variant)));
+
+    quote! {
+        impl #impl_generics ::ph_map::PhKey for #name #ty_generics #where_clause {
+            const COUNT: usize = #count;
+
+            fn index(&self) -> usize {
+                match self {
+                    #(#to_index,)*
+                }
+            }
+
+            fn from_index(index: usize) -> ::core::option::Option<Self> {
+                match index {
+                    #(#from_index,)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    }
+    .into()
+}
+
+/// Implements `ph_map::PhBytesKey` for a struct, encoding its fields in declaration order.
+#[proc_macro_derive(PhBytesKey)]
+pub fn derive_ph_bytes_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return syn::Error::new_spanned(
+            &input.ident,
+            "`PhBytesKey` can only be derived for structs",
+        )
+        .to_compile_error()
+        .into();
+    };
+
+    let fields = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => quote!(::ph_map::PhBytesKey::encode(&self.#ident, out)),
+            None => {
+                let index = Index::from(index);
+                quote!(::ph_map::PhBytesKey::encode(&self.#index, out))
+            }
+        });
+
+    quote! {
+        impl #impl_generics ::ph_map::PhBytesKey for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn encode(&self, out: &mut ::std::vec::Vec<u8>) {
+                #(#fields;)*
+            }
+        }
+    }
+    .into()
+}
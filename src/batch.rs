@@ -0,0 +1,108 @@
+use std::hash::Hash;
+
+use ph::BuildSeededHasher;
+
+use crate::PhMap;
+
+/// Number of keys which are hashed and prefetched before any of them are checked.
+pub(crate) const BATCH_SIZE: usize = 16;
+
+#[inline(always)]
+pub(crate) fn prefetch<T>(ptr: *const T) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        std::arch::x86_64::_mm_prefetch::<{ std::arch::x86_64::_MM_HINT_T0 }>(ptr.cast())
+    };
+
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = ptr;
+}
+
+impl<KOwned, V, KRef, H> PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    /// Returns `true` if every key in `keys` is in the map, stopping at the first one that
+    /// isn't. Keys are hashed in batches, so that the slots can be prefetched.
+    pub fn contains_all<I>(&self, keys: I) -> bool
+    where
+        I: IntoIterator,
+        I::Item: AsRef<KRef>,
+    {
+        !self.any_membership_is(keys, false)
+    }
+
+    /// Returns `true` if any key in `keys` is in the map, stopping at the first one that is.
+    /// Keys are hashed in batches, so that the slots can be prefetched.
+    pub fn contains_any<I>(&self, keys: I) -> bool
+    where
+        I: IntoIterator,
+        I::Item: AsRef<KRef>,
+    {
+        self.any_membership_is(keys, true)
+    }
+
+    /// Resolves the slot for each key in `keys`, calling `f` with the slot (if the key is in
+    /// the map) for each one in order. Stops early if `f` returns `false`.
+    pub(crate) fn for_each_slot_batched<I, F>(&self, keys: I, mut f: F)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<KRef>,
+        F: FnMut(Option<usize>) -> bool,
+    {
+        let hasher = self.to_index.hasher();
+        let mut keys = keys.into_iter();
+        let mut batch = [(0u64, usize::MAX); BATCH_SIZE];
+
+        loop {
+            let mut len = 0;
+
+            for key in keys.by_ref().take(BATCH_SIZE) {
+                let key = key.as_ref();
+                let hash = hasher.hash_one(key, 0);
+                let slot = self
+                    .to_index
+                    .get_with_top_level_hash(key, hash)
+                    .filter(|slot| *slot < self.top_level_hashes.len())
+                    .unwrap_or(usize::MAX);
+
+                if slot != usize::MAX {
+                    prefetch(self.top_level_hashes.ptr_at(slot));
+                }
+
+                batch[len] = (hash, slot);
+                len += 1;
+            }
+
+            for &(hash, slot) in &batch[..len] {
+                let found =
+                    self.top_level_hashes.matches(slot, hash) & self.occupied.contains(slot);
+
+                if !f(found.then_some(slot)) {
+                    return;
+                }
+            }
+
+            if len < BATCH_SIZE {
+                return;
+            }
+        }
+    }
+
+    fn any_membership_is<I>(&self, keys: I, is_member: bool) -> bool
+    where
+        I: IntoIterator,
+        I::Item: AsRef<KRef>,
+    {
+        let mut found = false;
+
+        self.for_each_slot_batched(keys, |slot| {
+            found = slot.is_some() == is_member;
+            !found
+        });
+
+        found
+    }
+}
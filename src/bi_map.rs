@@ -0,0 +1,175 @@
+use std::{collections::HashSet, fmt, hash::Hash};
+
+use crate::PhMap;
+
+/// A map which can be looked up by key or by value, with a perfect hash function in each
+/// direction. Values must be unique, like keys. Keys and values are each stored once, as the
+/// keys of the map for their direction, and both directions map to the entry's index.
+pub struct PhBiMap<K, V, KRef = K, VRef = V>
+where
+    KRef: ?Sized + Hash,
+    VRef: ?Sized + Hash,
+    K: AsRef<KRef>,
+    V: AsRef<VRef>,
+{
+    /// `by_key.keys` and `by_value.keys` are both in insertion order, so entry `i` is the
+    /// `i`th key of each.
+    by_key: PhMap<K, u32, KRef>,
+    by_value: PhMap<V, u32, VRef>,
+}
+
+impl<K, V, KRef, VRef> Default for PhBiMap<K, V, KRef, VRef>
+where
+    KRef: ?Sized + Hash,
+    VRef: ?Sized + Hash,
+    K: AsRef<KRef>,
+    V: AsRef<VRef>,
+{
+    fn default() -> Self {
+        Self {
+            by_key: PhMap::default(),
+            by_value: PhMap::default(),
+        }
+    }
+}
+
+impl<K, V, KRef, VRef> PhBiMap<K, V, KRef, VRef>
+where
+    KRef: ?Sized + Hash,
+    VRef: ?Sized + Hash,
+    K: AsRef<KRef>,
+    V: AsRef<VRef>,
+{
+    /// Adds the entry, returning `false` without rebuilding if `key` or `value` is already in
+    /// the map.
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        let len = self.len();
+        self.extend(std::iter::once((key, value)));
+
+        self.len() > len
+    }
+
+    #[inline]
+    pub fn get_by_key<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: ?Sized + AsRef<KRef>,
+    {
+        let idx = *self.by_key.get(key)?;
+
+        self.by_value.keys.get(idx as usize)
+    }
+
+    #[inline]
+    pub fn get_by_value<Q>(&self, value: &Q) -> Option<&K>
+    where
+        Q: ?Sized + AsRef<VRef>,
+    {
+        let idx = *self.by_value.get(value)?;
+
+        self.by_key.keys.get(idx as usize)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: ?Sized + AsRef<KRef>,
+    {
+        self.by_key.contains_key(key)
+    }
+
+    pub fn contains_value<Q>(&self, value: &Q) -> bool
+    where
+        Q: ?Sized + AsRef<VRef>,
+    {
+        self.by_value.contains_key(value)
+    }
+
+    /// Iterates over the entries in insertion order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator {
+        self.by_key.keys.iter().zip(&self.by_value.keys)
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_key.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_key.is_empty()
+    }
+}
+
+/// Entries whose key or value is already in the map, or in an earlier entry, are skipped. Both
+/// directions are rebuilt once.
+impl<K, V, KRef, VRef> Extend<(K, V)> for PhBiMap<K, V, KRef, VRef>
+where
+    KRef: ?Sized + Hash,
+    VRef: ?Sized + Hash,
+    K: AsRef<KRef>,
+    V: AsRef<VRef>,
+{
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (K, V)>,
+    {
+        // Keys with the same hash are the same key, as the map couldn't tell them apart.
+        let mut seen_keys = HashSet::new();
+        let mut seen_values = HashSet::new();
+        let mut keys = vec![];
+        let mut values = vec![];
+
+        for (key, value) in kv {
+            let key_hash = PhMap::<K, u32, KRef>::hash_key(key.as_ref());
+            let value_hash = PhMap::<V, u32, VRef>::hash_key(value.as_ref());
+
+            if self.contains_key(&key)
+                || self.contains_value(&value)
+                || seen_keys.contains(&key_hash)
+                || seen_values.contains(&value_hash)
+            {
+                continue;
+            }
+            seen_keys.insert(key_hash);
+            seen_values.insert(value_hash);
+
+            let idx = u32::try_from(self.len() + keys.len()).expect("more than `u32::MAX` entries");
+            keys.push((key, key_hash, idx));
+            values.push((value, value_hash, idx));
+        }
+
+        if !keys.is_empty() {
+            // Every hash was computed by `hash_key` for its key.
+            unsafe {
+                self.by_key.extend_prehashed(keys);
+                self.by_value.extend_prehashed(values);
+            }
+        }
+    }
+}
+
+impl<K, V, KRef, VRef> FromIterator<(K, V)> for PhBiMap<K, V, KRef, VRef>
+where
+    KRef: ?Sized + Hash,
+    VRef: ?Sized + Hash,
+    K: AsRef<KRef>,
+    V: AsRef<VRef>,
+{
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<K, V, KRef, VRef> fmt::Debug for PhBiMap<K, V, KRef, VRef>
+where
+    KRef: ?Sized + Hash,
+    VRef: ?Sized + Hash,
+    K: AsRef<KRef> + fmt::Debug,
+    V: AsRef<VRef> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,175 @@
+use std::{fmt, hash::Hash, marker::PhantomData};
+
+use ph::BuildSeededHasher;
+
+use crate::{BuildError, KeyHasher, MAX_SEED_BITS, PhMap, Profile};
+
+/// Builds a [`PhMap`] with the parameters of its PHast function set individually, rather than
+/// through a [`Profile`] preset alone. `H` is the hasher of the maps it builds, which defaults
+/// to the one chosen by the `gxhash` and `portable-hash` features.
+pub struct PhMapBuilder<H = KeyHasher> {
+    profile: Profile,
+    bits_per_seed: Option<u8>,
+    bucket_size100: Option<u16>,
+    verify_slots: bool,
+    _hasher: PhantomData<fn() -> H>,
+}
+
+impl PhMapBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<H> PhMapBuilder<H> {
+    /// Starts from the parameters of `profile`. Parameters which are set individually
+    /// override those of the profile, and the rest are still chosen by it.
+    pub fn profile(self, profile: Profile) -> Self {
+        Self { profile, ..self }
+    }
+
+    /// Bits per seed of the function, clamped to between 1 and 16. More bits use more memory
+    /// but build faster, and place more keys in the first level.
+    pub fn bits_per_seed(self, bits: u8) -> Self {
+        Self {
+            bits_per_seed: Some(bits.clamp(1, MAX_SEED_BITS)),
+            ..self
+        }
+    }
+
+    /// Average number of keys per bucket, times 100. Larger buckets use less memory but
+    /// build slower. Defaults to the bucket size of the profile.
+    pub fn bucket_size100(self, bucket_size100: u16) -> Self {
+        Self {
+            bucket_size100: Some(bucket_size100.max(1)),
+            ..self
+        }
+    }
+
+    /// Hashes keys with `H2` rather than the default hasher. Keys are hashed with
+    /// `H2::default()`, so every map built with the same hasher places keys the same way.
+    pub fn hasher<H2>(self) -> PhMapBuilder<H2> {
+        PhMapBuilder {
+            profile: self.profile,
+            bits_per_seed: self.bits_per_seed,
+            bucket_size100: self.bucket_size100,
+            verify_slots: self.verify_slots,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Whether every build and rebuild checks that no two keys were given the same slot,
+    /// which can only happen if their hashes collide, and fails with
+    /// [`BuildError::SharedSlot`] if they were. Without this, the check is only done in
+    /// builds with debug assertions.
+    pub fn verify_slots(self, verify_slots: bool) -> Self {
+        Self {
+            verify_slots,
+            ..self
+        }
+    }
+
+    /// The profile maps built by this builder use, including for rebuilds after they're
+    /// built.
+    pub fn to_profile(&self) -> Profile {
+        if self.bits_per_seed.is_none() && self.bucket_size100.is_none() {
+            return self.profile;
+        }
+
+        let base = match self.profile {
+            Profile::Balanced => &Profile::Balanced,
+            Profile::MinMemory => &Profile::MinMemory,
+            Profile::MaxLookupSpeed => &Profile::MaxLookupSpeed,
+            Profile::FastBuild => &Profile::FastBuild,
+            Profile::Custom {
+                bits_per_seed,
+                bucket_size100,
+                base,
+            } => {
+                return Profile::Custom {
+                    bits_per_seed: self.bits_per_seed.or(bits_per_seed),
+                    bucket_size100: self.bucket_size100.or(bucket_size100),
+                    base,
+                };
+            }
+        };
+
+        Profile::Custom {
+            bits_per_seed: self.bits_per_seed,
+            bucket_size100: self.bucket_size100,
+            base,
+        }
+    }
+
+    pub fn build<KOwned, V, KRef, I>(&self, kvs: I) -> PhMap<KOwned, V, KRef, H>
+    where
+        KRef: ?Sized + Hash,
+        KOwned: AsRef<KRef>,
+        H: BuildSeededHasher + Default,
+        I: IntoIterator<Item = (KOwned, V)>,
+    {
+        let mut map = self.empty_map();
+        map.extend(kvs);
+        map
+    }
+
+    /// Like [`PhMapBuilder::build`], but returns an error instead of panicking if the map
+    /// can't be built.
+    pub fn try_build<KOwned, V, KRef, I>(
+        &self,
+        kvs: I,
+    ) -> Result<PhMap<KOwned, V, KRef, H>, BuildError>
+    where
+        KRef: ?Sized + Hash,
+        KOwned: AsRef<KRef>,
+        H: BuildSeededHasher + Default,
+        I: IntoIterator<Item = (KOwned, V)>,
+    {
+        let mut map = self.empty_map();
+        map.try_extend(kvs)?;
+        Ok(map)
+    }
+
+    fn empty_map<KOwned, V, KRef>(&self) -> PhMap<KOwned, V, KRef, H>
+    where
+        KRef: ?Sized + Hash,
+        KOwned: AsRef<KRef>,
+        H: BuildSeededHasher + Default,
+    {
+        let mut map = PhMap::with_profile(self.to_profile());
+        map.verify_slots = self.verify_slots;
+        map
+    }
+}
+
+impl<H> Default for PhMapBuilder<H> {
+    fn default() -> Self {
+        Self {
+            profile: Profile::default(),
+            bits_per_seed: None,
+            bucket_size100: None,
+            verify_slots: false,
+            _hasher: PhantomData,
+        }
+    }
+}
+
+impl<H> Clone for PhMapBuilder<H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<H> Copy for PhMapBuilder<H> {}
+
+impl<H> fmt::Debug for PhMapBuilder<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PhMapBuilder")
+            .field("profile", &self.profile)
+            .field("bits_per_seed", &self.bits_per_seed)
+            .field("bucket_size100", &self.bucket_size100)
+            .field("verify_slots", &self.verify_slots)
+            .field("hasher", &std::any::type_name::<H>())
+            .finish()
+    }
+}
@@ -0,0 +1,840 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{Index, IndexMut};
+
+use crate::key_compression::{MAX_POSITIONS, Selection};
+use itertools::Itertools;
+use ph::BuildSeededHasher;
+
+use crate::{
+    BuildError, BytesIter, Duplicates, KeyCompression, MapOptions, PhMap,
+    batch::{BATCH_SIZE, prefetch},
+    build_failed,
+    inline_bytes::InlineBytes,
+};
+
+/// Marks a key length in [`PhBytesMap::shard_by_len`] which has no shard.
+const NO_SHARD: u32 = u32::MAX;
+
+/// Key lengths below this are looked up in [`PhBytesMap::shard_by_len`], and longer ones in
+/// [`PhBytesMap::long_shards`], so that a single long key doesn't grow a table entry for
+/// every shorter length.
+const DENSE_LENS: usize = 64;
+
+/// All keys of a [`PhBytesMap`] with a single length. As every key is the same length, the
+/// selected bytes are always in bounds for every key.
+pub(crate) struct Shard<V> {
+    pub(crate) selection: Selection,
+    /// Bytes at the prefilter position of any key in this shard, if the map has a prefilter.
+    prefilter: ByteSet,
+    /// Length of every key in this shard.
+    key_len: usize,
+    /// The full keys, `key_len` bytes each, in the same order as the keys of `inner_map`.
+    /// Keeping them in one buffer means keys never allocate individually, and the selection
+    /// is stored once here rather than with every key.
+    pub(crate) full_keys: Vec<u8>,
+    /// Keyed by the selected bytes of each key.
+    pub(crate) inner_map: PhMap<InlineBytes, V, [u8]>,
+}
+
+/// The bytes of `key` which are hashed under `selection`. `key` must be as long as every key of
+/// the shard the selection is for.
+fn selected(key: &[u8], selection: &Selection) -> InlineBytes {
+    let mut buf = [0; MAX_POSITIONS];
+    let selected = selection
+        .select(key, &mut buf)
+        .expect("every key in a shard is long enough for its selection");
+
+    InlineBytes::from(selected)
+}
+
+/// A set of bytes, stored as a 256-bit bitmap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ByteSet([u64; 4]);
+
+impl ByteSet {
+    fn insert(&mut self, byte: u8) {
+        self.0[byte as usize / 64] |= 1 << (byte % 64);
+    }
+
+    #[inline]
+    fn contains(&self, byte: u8) -> bool {
+        self.0[byte as usize / 64] & (1 << (byte % 64)) != 0
+    }
+}
+
+/// Rebuilds the hash function from the cloned keys, keeping the same selection, as the function
+/// itself can't be cloned.
+impl<V: Clone> Clone for Shard<V> {
+    fn clone(&self) -> Self {
+        let mut inner_map = PhMap::with_profile(self.inner_map.profile);
+        if !self.inner_map.is_empty() {
+            inner_map.extend(self.inner_map.iter());
+        }
+
+        Self {
+            selection: self.selection.clone(),
+            prefilter: self.prefilter,
+            key_len: self.key_len,
+            full_keys: self.full_keys.clone(),
+            inner_map,
+        }
+    }
+}
+
+impl<V> Shard<V> {
+    fn new(key_len: usize) -> Self {
+        Self {
+            selection: Selection::Range(0..0),
+            prefilter: ByteSet::default(),
+            key_len,
+            full_keys: vec![],
+            inner_map: PhMap::default(),
+        }
+    }
+
+    /// The full key at `position` in insertion order.
+    #[inline]
+    pub(crate) fn key(&self, position: usize) -> Option<&[u8]> {
+        if position >= self.inner_map.len() {
+            return None;
+        }
+
+        self.full_keys
+            .get(position * self.key_len..)?
+            .get(..self.key_len)
+    }
+
+    /// The full key stored in slot `idx`.
+    #[inline]
+    fn key_in_slot(&self, idx: usize) -> Option<&[u8]> {
+        self.key(*self.inner_map.key_indices.get(idx)? as usize)
+    }
+
+    /// The full keys in insertion order.
+    fn keys(&self) -> impl Iterator<Item = &[u8]> + Clone {
+        (0..self.inner_map.len()).filter_map(|position| self.key(position))
+    }
+
+    fn extend(
+        &mut self,
+        kvs: Vec<(InlineBytes, V)>,
+        prefilter: Option<usize>,
+        compression: KeyCompression,
+    ) {
+        if let Some(position) = prefilter {
+            for (k, _) in &kvs {
+                if let Some(byte) = k.as_ref().get(position) {
+                    self.prefilter.insert(*byte);
+                }
+            }
+        }
+
+        let selection = compression.select(self.keys().chain(kvs.iter().map(|(k, _)| k.as_ref())));
+
+        let existing = if selection == self.selection {
+            vec![]
+        } else {
+            // The existing keys are hashed by the old selection, so they all have to be
+            // re-selected. This is done as one batch with the new keys so that the function is
+            // only rebuilt once.
+            let full_keys = std::mem::take(&mut self.full_keys);
+            let key_len = self.key_len;
+
+            self.inner_map
+                .drain()
+                .enumerate()
+                .map(|(position, (_, v))| {
+                    let key = &full_keys[position * key_len..][..key_len];
+                    self.full_keys.extend_from_slice(key);
+
+                    (selected(key, &selection), v)
+                })
+                .collect()
+        };
+
+        let full_keys = &mut self.full_keys;
+        let added = kvs.into_iter().map(|(k, v)| {
+            full_keys.extend_from_slice(k.as_ref());
+
+            (selected(k.as_ref(), &selection), v)
+        });
+        self.inner_map.extend(existing.into_iter().chain(added));
+
+        self.selection = selection;
+    }
+
+    /// Reselects the remaining keys and rebuilds the function over them, recomputing the
+    /// prefilter so that bytes only seen in removed keys no longer pass it.
+    fn compact(&mut self, prefilter: Option<usize>, compression: KeyCompression) {
+        let mut bytes = ByteSet::default();
+        if let Some(position) = prefilter {
+            for byte in self.keys().filter_map(|key| key.get(position)) {
+                bytes.insert(*byte);
+            }
+        }
+        self.prefilter = bytes;
+
+        self.extend(vec![], None, compression);
+    }
+
+    /// Removes every key, keeping the allocations for the keys, values and hashes.
+    fn clear(&mut self) {
+        self.selection = Selection::Range(0..0);
+        self.prefilter = ByteSet::default();
+        self.full_keys.clear();
+        self.inner_map.clear();
+    }
+
+    /// Removes the key at `position` in insertion order from `full_keys`, after the key has
+    /// been removed from `inner_map`. Like the keys of `inner_map`, the last key takes its
+    /// place.
+    fn remove_full_key(&mut self, position: usize) {
+        let last = self.full_keys.len() - self.key_len;
+        self.full_keys.copy_within(last.., position * self.key_len);
+        self.full_keys.truncate(last);
+    }
+}
+
+/// A map keyed by byte strings, which only hashes the smallest range of bytes that
+/// distinguishes the keys. Keys are sharded by length, so each length gets its own range. The
+/// full keys are kept for iteration, but lookups only compare the range unless the map was
+/// created with [`MapOptions::verify_keys`].
+#[derive(Clone)]
+pub struct PhBytesMap<V> {
+    /// Index into `shards` for each key length below [`DENSE_LENS`], or [`NO_SHARD`].
+    shard_by_len: Vec<u32>,
+    /// Index into `shards` for each key length of at least [`DENSE_LENS`] which has a shard.
+    long_shards: BTreeMap<usize, u32>,
+    pub(crate) shards: Vec<Shard<V>>,
+    /// The prefilter position is checked against each shard's [`Shard::prefilter`] before
+    /// hashing.
+    pub(crate) options: MapOptions,
+}
+
+impl<V> Default for PhBytesMap<V> {
+    fn default() -> Self {
+        Self {
+            shard_by_len: vec![],
+            long_shards: BTreeMap::new(),
+            shards: vec![],
+            options: MapOptions::default(),
+        }
+    }
+}
+
+impl<V> PhBytesMap<V> {
+    pub fn with_options(options: MapOptions) -> Self {
+        Self {
+            options,
+            ..Default::default()
+        }
+    }
+
+    pub fn options(&self) -> MapOptions {
+        self.options
+    }
+
+    #[inline]
+    fn passes_prefilter(&self, shard: &Shard<V>, key: &[u8]) -> bool {
+        match self.options.prefilter {
+            Some(position) => key
+                .get(position)
+                .is_none_or(|byte| shard.prefilter.contains(*byte)),
+            None => true,
+        }
+    }
+
+    /// Inserts `value`, returning the old value if `key` was already in the map. The map is
+    /// only rebuilt if `key` is new. An existing key's value is always replaced, as the
+    /// [`Duplicates`] policy only applies to keys added in bulk.
+    pub fn insert(&mut self, key: Vec<u8>, value: V) -> Option<V> {
+        if let Some((shard, idx)) = self.find_exact(&key) {
+            return self.shards[shard].inner_map.values[idx].replace(value);
+        }
+
+        self.extend(std::iter::once((key, value)));
+        None
+    }
+
+    /// Iterates over the entries, grouped by key length in the order each length was first
+    /// inserted, and in insertion order within each length.
+    pub fn iter(&self) -> BytesIter<'_, V> {
+        BytesIter {
+            shards: self.shards.iter(),
+            shard: None,
+            entries: None,
+            position: 0,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &[u8]> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.inner_map.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| shard.inner_map.is_empty())
+    }
+
+    /// Index into `shards` of the shard for keys of length `len`. This may be [`NO_SHARD`],
+    /// which is always out of bounds of `shards`.
+    #[inline]
+    fn shard_index(&self, len: usize) -> Option<usize> {
+        let shard = if len < DENSE_LENS {
+            self.shard_by_len.get(len)
+        } else {
+            self.long_shards.get(&len)
+        };
+
+        shard.map(|shard| *shard as usize)
+    }
+
+    #[inline]
+    fn shard(&self, len: usize) -> Option<&Shard<V>> {
+        self.shards.get(self.shard_index(len)?)
+    }
+
+    #[inline]
+    fn shard_mut(&mut self, len: usize) -> Option<&mut Shard<V>> {
+        let shard = self.shard_index(len)?;
+
+        self.shards.get_mut(shard)
+    }
+
+    /// Number of keys of length `len`, which is also the position the next key of that length
+    /// will have in [`PhBytesMap::key_of_len`].
+    pub(crate) fn count_of_len(&self, len: usize) -> usize {
+        self.shard(len).map_or(0, |shard| shard.inner_map.len())
+    }
+
+    /// The key at `position` among the keys of length `len`, which are kept in insertion
+    /// order. Removing a key moves the later keys of its length down by one.
+    pub(crate) fn key_of_len(&self, len: usize, position: usize) -> Option<&[u8]> {
+        self.shard(len)?.key(position)
+    }
+
+    /// Key lengths up to `max_len` which have a shard, longest first.
+    pub(crate) fn lengths_up_to(&self, max_len: usize) -> impl Iterator<Item = usize> + '_ {
+        let end = self.shard_by_len.len().min(max_len.saturating_add(1));
+        let long = self
+            .long_shards
+            .range(DENSE_LENS..)
+            .rev()
+            .map(|(len, _)| *len)
+            .skip_while(move |len| *len > max_len);
+
+        let dense = self.shard_by_len[..end]
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, shard)| **shard != NO_SHARD)
+            .map(|(len, _)| len);
+
+        long.chain(dense)
+    }
+
+    /// # Safety
+    /// There must be a shard for keys of length `len`.
+    #[inline]
+    unsafe fn shard_unchecked(&self, len: usize) -> &Shard<V> {
+        unsafe {
+            self.shards
+                .get_unchecked(self.shard_index(len).unwrap_unchecked())
+        }
+    }
+
+    /// # Safety
+    /// There must be a shard for keys of length `len`.
+    #[inline]
+    unsafe fn shard_unchecked_mut(&mut self, len: usize) -> &mut Shard<V> {
+        unsafe {
+            let shard = self.shard_index(len).unwrap_unchecked();
+
+            self.shards.get_unchecked_mut(shard)
+        }
+    }
+
+    /// Keys are looked up by length first, so a key which is too short to contain the range
+    /// of any shard returns `None` rather than being sliced.
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+    pub fn get<K>(&self, key: &K) -> Option<&V>
+    where
+        K: ?Sized + AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let shard = self.shard(key.len())?;
+        let idx = self.find_slot(shard, key)?;
+
+        shard.inner_map.values.get(idx)?.as_ref()
+    }
+
+    /// The slot of `key` in `shard`, which must be the shard for its length.
+    #[inline]
+    fn find_slot(&self, shard: &Shard<V>, key: &[u8]) -> Option<usize> {
+        if !self.passes_prefilter(shard, key) {
+            return None;
+        }
+
+        let mut buf = [0; MAX_POSITIONS];
+        let idx = shard
+            .inner_map
+            .find_index(shard.selection.select(key, &mut buf)?)?;
+
+        self.holds_key(shard, key, idx).then_some(idx)
+    }
+
+    /// Whether the key in slot `idx` of `shard` is `key`. This is always `true` unless the
+    /// map verifies keys.
+    #[inline]
+    fn holds_key(&self, shard: &Shard<V>, key: &[u8], idx: usize) -> bool {
+        if !self.options.verify_keys {
+            return true;
+        }
+
+        shard.key_in_slot(idx) == Some(key)
+    }
+
+    /// Looks up every key in `keys`, returning the values in the same order. Keys are selected
+    /// and hashed in batches, and their slots prefetched before any of them are checked, which
+    /// is faster than calling [`PhBytesMap::get`] for each key when there are many.
+    pub fn get_batch<K>(&self, keys: &[K]) -> Vec<Option<&V>>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.get_batch_from(keys.iter().map(AsRef::as_ref))
+    }
+
+    pub(crate) fn get_batch_from<'k, I>(&self, keys: I) -> Vec<Option<&V>>
+    where
+        I: ExactSizeIterator<Item = &'k [u8]>,
+    {
+        let mut values = Vec::with_capacity(keys.len());
+        let mut keys = keys;
+        let mut batch = [None; BATCH_SIZE];
+
+        loop {
+            let mut len = 0;
+
+            for key in keys.by_ref().take(BATCH_SIZE) {
+                batch[len] = self
+                    .shard(key.len())
+                    .filter(|shard| self.passes_prefilter(shard, key))
+                    .and_then(|shard| {
+                        let inner = &shard.inner_map;
+                        let mut buf = [0; MAX_POSITIONS];
+                        let selected = shard.selection.select(key, &mut buf)?;
+                        let hash = inner.to_index.hasher().hash_one(selected, 0);
+                        let idx = inner.to_index.get_with_top_level_hash(selected, hash)?;
+
+                        prefetch(inner.top_level_hashes.ptr_at(idx));
+                        prefetch(inner.values.as_ptr().wrapping_add(idx));
+
+                        Some((shard, key, hash, idx))
+                    });
+                len += 1;
+            }
+
+            values.extend(batch[..len].iter().map(|found| {
+                let (shard, key, hash, idx) = (*found)?;
+                let inner = &shard.inner_map;
+                let found =
+                    inner.top_level_hashes.matches(idx, hash) && self.holds_key(shard, key, idx);
+
+                inner.values.get(idx).filter(|_| found)?.as_ref()
+            }));
+
+            if len < BATCH_SIZE {
+                return values;
+            }
+        }
+    }
+
+    /// Whether `key` is in the map. This does the same checks as [`PhBytesMap::get`], but
+    /// doesn't read the value.
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+    pub fn contains_key<K>(&self, key: &K) -> bool
+    where
+        K: ?Sized + AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+
+        self.shard(key.len())
+            .is_some_and(|shard| self.find_slot(shard, key).is_some())
+    }
+
+    /// # Safety
+    /// `key` must be in the map.
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+    pub unsafe fn get_unchecked<K>(&self, key: &K) -> &V
+    where
+        K: ?Sized + AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+
+        unsafe {
+            let shard = self.shard_unchecked(key.len());
+
+            shard.inner_map.get_unchecked(
+                shard
+                    .selection
+                    .select_unchecked(key, &mut [0; MAX_POSITIONS]),
+            )
+        }
+    }
+
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+    pub fn get_mut<K>(&mut self, key: &K) -> Option<&mut V>
+    where
+        K: ?Sized + AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let idx = self.find_slot(self.shard(key.len())?, key)?;
+
+        self.shard_mut(key.len())?
+            .inner_map
+            .values
+            .get_mut(idx)?
+            .as_mut()
+    }
+
+    /// # Safety
+    /// `key` must be in the map.
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+    pub unsafe fn get_unchecked_mut<K>(&mut self, key: &K) -> &mut V
+    where
+        K: ?Sized + AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+
+        unsafe {
+            let shard = self.shard_unchecked_mut(key.len());
+
+            shard.inner_map.get_unchecked_mut(
+                shard
+                    .selection
+                    .select_unchecked(key, &mut [0; MAX_POSITIONS]),
+            )
+        }
+    }
+
+    /// Removes `key` from the map, returning its value. Unlike [`PhBytesMap::get`], this always
+    /// compares the whole key, so a key which only shares the discriminating range with a key
+    /// in the map doesn't remove it. The discriminating range of the key's shard isn't narrowed
+    /// until the shard is next extended or [`PhBytesMap::maintain`] is called.
+    pub fn remove<K>(&mut self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let (shard, idx) = self.find_exact(key)?;
+
+        let shard = &mut self.shards[shard];
+        let position = shard.inner_map.key_indices[idx] as usize;
+        let mut buf = [0; MAX_POSITIONS];
+        let value = shard
+            .inner_map
+            .remove(shard.selection.select(key, &mut buf)?);
+        shard.remove_full_key(position);
+
+        value
+    }
+
+    /// Compacts every shard which has had keys removed since it was last built, narrowing its
+    /// discriminating range to the remaining keys and reclaiming their slots, and returns
+    /// whether any shard was rebuilt. Meant to be called while the map is idle, so that the
+    /// work deferred by [`PhBytesMap::remove`] isn't done by the next extend.
+    pub fn maintain(&mut self) -> bool {
+        let mut rebuilt = false;
+        for shard in &mut self.shards {
+            if shard.inner_map.removed > 0 {
+                shard.compact(self.options.prefilter, self.options.compression);
+                rebuilt = true;
+            }
+        }
+
+        rebuilt
+    }
+
+    /// Removes every entry. Each shard is kept with its allocations, so adding keys of the
+    /// same lengths again doesn't reallocate.
+    pub fn clear(&mut self) {
+        for shard in &mut self.shards {
+            shard.clear();
+        }
+    }
+
+    /// Keeps only the entries for which `f` returns `true`. Only the shards which lose entries
+    /// are rebuilt. Like [`PhBytesMap::remove`], this leaves the discriminating ranges as they
+    /// are until the next extend.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&[u8], &mut V) -> bool,
+    {
+        for shard in &mut self.shards {
+            let key_len = shard.key_len;
+
+            let keep = shard
+                .inner_map
+                .iter_mut()
+                .enumerate()
+                .map(|(position, (_, value))| {
+                    f(&shard.full_keys[position * key_len..][..key_len], value)
+                })
+                .collect::<Vec<_>>();
+            if keep.iter().all(|keep| *keep) {
+                continue;
+            }
+
+            let full_keys = std::mem::take(&mut shard.full_keys);
+            let kept = shard
+                .inner_map
+                .drain()
+                .zip(keep)
+                .enumerate()
+                .filter_map(|(position, ((selected, value), keep))| {
+                    let key = &full_keys[position * key_len..][..key_len];
+                    keep.then(|| {
+                        shard.full_keys.extend_from_slice(key);
+                        (selected, value)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            if !kept.is_empty() {
+                shard.inner_map.extend(kept);
+            }
+        }
+    }
+
+    /// Leaks the map, returning a shared reference which lives for the rest of the program.
+    pub fn leak(self) -> &'static Self
+    where
+        Self: 'static,
+    {
+        Box::leak(Box::new(self))
+    }
+
+    pub fn get_copied<K>(&self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<[u8]>,
+        V: Copy,
+    {
+        self.get(key).copied()
+    }
+
+    pub fn get_cloned<K>(&self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<[u8]>,
+        V: Clone,
+    {
+        self.get(key).cloned()
+    }
+}
+
+impl<V> PhBytesMap<V> {
+    /// Like [`Extend::extend`], but returns an error instead of panicking if any key is
+    /// already in the map or appears more than once. On error, the map is unchanged.
+    pub fn try_extend<KV>(&mut self, kv: KV) -> Result<(), BuildError>
+    where
+        KV: IntoIterator<Item = (Vec<u8>, V)>,
+    {
+        self.try_extend_bytes(kv.into_iter().map(|(k, v)| (InlineBytes::from(k), v)))
+    }
+
+    pub(crate) fn extend_bytes<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (InlineBytes, V)>,
+    {
+        if let Err(e) = self.try_extend_bytes(kv) {
+            build_failed(e);
+        }
+    }
+
+    pub(crate) fn try_extend_bytes<KV>(&mut self, kv: KV) -> Result<(), BuildError>
+    where
+        KV: IntoIterator<Item = (InlineBytes, V)>,
+    {
+        let mut kvs_by_len = BTreeMap::<usize, Vec<(InlineBytes, V)>>::new();
+        for (k, v) in kv {
+            kvs_by_len.entry(k.as_ref().len()).or_default().push((k, v));
+        }
+
+        if self.options.duplicates != Duplicates::Error {
+            for kvs in kvs_by_len.values_mut() {
+                self.resolve_duplicates(kvs);
+            }
+        }
+
+        // Every selection widens until it tells the keys apart, which only fails for equal
+        // keys, so check for those before changing any shard.
+        let duplicates = kvs_by_len
+            .iter()
+            .flat_map(|(len, kvs)| {
+                let existing = self.shard(*len).into_iter().flat_map(Shard::keys);
+
+                existing
+                    .chain(kvs.iter().map(|(k, _)| k.as_ref()))
+                    .duplicates()
+            })
+            .map(<[u8]>::to_vec)
+            .collect::<Vec<_>>();
+        if !duplicates.is_empty() {
+            return Err(BuildError::DuplicateKeys { keys: duplicates });
+        }
+
+        for (len, kvs) in kvs_by_len {
+            let shard = match self
+                .shard_index(len)
+                .filter(|shard| *shard < self.shards.len())
+            {
+                Some(shard) => shard,
+                None => self.add_shard(len),
+            };
+
+            self.shards[shard].extend(kvs, self.options.prefilter, self.options.compression);
+        }
+
+        Ok(())
+    }
+}
+
+impl<V> PhBytesMap<V> {
+    /// Adds an empty shard for keys of length `len`, returning its index.
+    fn add_shard(&mut self, len: usize) -> usize {
+        let shard = self.shards.len();
+        let index = u32::try_from(shard).unwrap();
+
+        if len < DENSE_LENS {
+            if self.shard_by_len.len() <= len {
+                self.shard_by_len.resize(len + 1, NO_SHARD);
+            }
+            self.shard_by_len[len] = index;
+        } else {
+            self.long_shards.insert(len, index);
+        }
+
+        self.shards.push(Shard::new(len));
+
+        shard
+    }
+
+    /// Removes the keys from `kvs` which are already in the map, or appear earlier in `kvs`.
+    /// With [`Duplicates::KeepLast`] their values replace the values of those keys first.
+    fn resolve_duplicates(&mut self, kvs: &mut Vec<(InlineBytes, V)>) {
+        let keep_last = self.options.duplicates == Duplicates::KeepLast;
+
+        // The index of the first entry with each entry's key, if it's not that entry.
+        let firsts = {
+            let mut firsts = HashMap::new();
+            kvs.iter()
+                .enumerate()
+                .map(|(i, (k, _))| Some(*firsts.entry(k.as_ref()).or_insert(i)).filter(|j| *j != i))
+                .collect::<Vec<_>>()
+        };
+
+        let mut entries = std::mem::take(kvs)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        for (i, first) in firsts.into_iter().enumerate() {
+            let Some(first) = first else { continue };
+            let Some((_, value)) = entries[i].take() else {
+                continue;
+            };
+
+            if keep_last && let Some((_, first_value)) = &mut entries[first] {
+                *first_value = value;
+            }
+        }
+
+        for (k, v) in entries.into_iter().flatten() {
+            match self.find_exact(k.as_ref()) {
+                Some((shard, idx)) => {
+                    if keep_last {
+                        self.shards[shard].inner_map.values[idx] = Some(v);
+                    }
+                }
+                None => kvs.push((k, v)),
+            }
+        }
+    }
+
+    /// The shard and slot of `key`, comparing the whole key even if the map doesn't verify
+    /// keys.
+    fn find_exact(&self, key: &[u8]) -> Option<(usize, usize)> {
+        let shard = self.shard_index(key.len())?;
+        let stored = self.shards.get(shard)?;
+
+        let mut buf = [0; MAX_POSITIONS];
+        let idx = stored
+            .inner_map
+            .find_index(stored.selection.select(key, &mut buf)?)?;
+
+        (stored.key_in_slot(idx) == Some(key)).then_some((shard, idx))
+    }
+}
+
+impl<V> Extend<(Vec<u8>, V)> for PhBytesMap<V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (Vec<u8>, V)>,
+    {
+        self.extend_bytes(kv.into_iter().map(|(k, v)| (InlineBytes::from(k), v)))
+    }
+}
+
+/// Byte string literals are copied straight into their shard's key buffer, without an
+/// allocation for each key.
+impl<V> Extend<(&'static [u8], V)> for PhBytesMap<V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (&'static [u8], V)>,
+    {
+        self.extend_bytes(kv.into_iter().map(|(k, v)| (InlineBytes::Static(k), v)))
+    }
+}
+
+impl<V> Extend<(Cow<'static, [u8]>, V)> for PhBytesMap<V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (Cow<'static, [u8]>, V)>,
+    {
+        self.extend_bytes(kv.into_iter().map(|(k, v)| {
+            let k = match k {
+                Cow::Borrowed(k) => InlineBytes::Static(k),
+                Cow::Owned(k) => InlineBytes::from(k),
+            };
+
+            (k, v)
+        }))
+    }
+}
+
+impl<K, V> Index<&K> for PhBytesMap<V>
+where
+    K: ?Sized + AsRef<[u8]>,
+{
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key not in map")
+    }
+}
+
+impl<K, V> IndexMut<&K> for PhBytesMap<V>
+where
+    K: ?Sized + AsRef<[u8]>,
+{
+    fn index_mut(&mut self, key: &K) -> &mut V {
+        self.get_mut(key).expect("key not in map")
+    }
+}
@@ -0,0 +1,155 @@
+use std::{fmt, hash::Hash};
+
+use crate::PhMap;
+
+/// A compression scheme for the values of a [`PhCompressedMap`].
+pub trait Codec {
+    type Error;
+
+    /// Appends the compressed form of `input` to `output`.
+    fn compress(&self, input: &[u8], output: &mut Vec<u8>);
+
+    /// Appends the decompressed form of `input` to `output`.
+    fn decompress(&self, input: &[u8], output: &mut Vec<u8>) -> Result<(), Self::Error>;
+}
+
+/// A [`Codec`] which stores each run of a repeated byte as the length of the run followed by
+/// the byte. This only shrinks values with long runs, such as padded or sparse data, but needs
+/// no dependencies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunLength;
+
+/// Input to [`RunLength`] which it didn't compress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunLengthError;
+
+impl fmt::Display for RunLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("run-length encoded input is truncated or has an empty run")
+    }
+}
+
+impl std::error::Error for RunLengthError {}
+
+impl Codec for RunLength {
+    type Error = RunLengthError;
+
+    fn compress(&self, input: &[u8], output: &mut Vec<u8>) {
+        for run in input.chunk_by(|a, b| a == b) {
+            for part in run.chunks(u8::MAX.into()) {
+                output.extend([part.len() as u8, part[0]]);
+            }
+        }
+    }
+
+    fn decompress(&self, input: &[u8], output: &mut Vec<u8>) -> Result<(), Self::Error> {
+        let runs = input.chunks_exact(2);
+        if !runs.remainder().is_empty() {
+            return Err(RunLengthError);
+        }
+
+        for run in runs {
+            let (len, byte) = match *run {
+                [len @ 1..=u8::MAX, byte] => (len, byte),
+                _ => return Err(RunLengthError),
+            };
+            output.extend(std::iter::repeat_n(byte, len.into()));
+        }
+
+        Ok(())
+    }
+}
+
+/// A map whose byte-string values are stored compressed and only decompressed on access,
+/// for large values which are rarely read.
+pub struct PhCompressedMap<KOwned, C, KRef = KOwned>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    inner: PhMap<KOwned, Box<[u8]>, KRef>,
+    codec: C,
+}
+
+impl<KOwned, C, KRef> PhCompressedMap<KOwned, C, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    C: Codec,
+{
+    pub fn new(codec: C) -> Self {
+        Self {
+            inner: Default::default(),
+            codec,
+        }
+    }
+
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Compresses and inserts `value` for `key`, replacing the value if `key` is already in the
+    /// map. The map is only rebuilt if `key` is new.
+    pub fn insert<T>(&mut self, key: KOwned, value: T)
+    where
+        T: AsRef<[u8]>,
+    {
+        let mut compressed = vec![];
+        self.codec.compress(value.as_ref(), &mut compressed);
+
+        self.inner.insert(key, compressed.into_boxed_slice());
+    }
+
+    /// Gets the value for `key` without decompressing it.
+    pub fn get_compressed<K>(&self, key: &K) -> Option<&[u8]>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.inner.get(key).map(|value| &value[..])
+    }
+
+    /// Decompresses the value for `key` into `output`, replacing its contents. Returns `None`
+    /// if `key` is not in the map.
+    pub fn get_into<K>(&self, key: &K, output: &mut Vec<u8>) -> Option<Result<(), C::Error>>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let compressed = self.inner.get(key)?;
+
+        output.clear();
+
+        Some(self.codec.decompress(compressed, output))
+    }
+
+    /// Decompresses the value for `key` into a new buffer.
+    pub fn get_decompressed<K>(&self, key: &K) -> Option<Result<Vec<u8>, C::Error>>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let mut output = vec![];
+
+        Some(self.get_into(key, &mut output)?.map(|()| output))
+    }
+}
+
+impl<KOwned, C, KRef, T> Extend<(KOwned, T)> for PhCompressedMap<KOwned, C, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    C: Codec,
+    T: AsRef<[u8]>,
+{
+    fn extend<I>(&mut self, kv: I)
+    where
+        I: IntoIterator<Item = (KOwned, T)>,
+    {
+        let codec = &self.codec;
+
+        self.inner.extend(kv.into_iter().map(|(key, value)| {
+            let mut compressed = vec![];
+            codec.compress(value.as_ref(), &mut compressed);
+
+            (key, compressed.into_boxed_slice())
+        }));
+    }
+}
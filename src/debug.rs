@@ -0,0 +1,100 @@
+use std::{fmt, hash::Hash};
+
+use dyn_size_of::GetSize;
+use ph::BuildSeededHasher;
+
+use crate::{PhBytesMap, PhMap, PhStrMap};
+
+/// Formats like a `HashMap`. The alternate form (`{:#?}`) also includes the number of slots
+/// and the size of the perfect hash function.
+impl<KOwned, V, KRef, H> fmt::Debug for PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef> + fmt::Debug,
+    V: fmt::Debug,
+    H: BuildSeededHasher + Default,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = fmt::from_fn(|f| f.debug_map().entries(self.iter()).finish());
+
+        if !f.alternate() {
+            return fmt::Debug::fmt(&entries, f);
+        }
+
+        f.debug_struct("PhMap")
+            .field("entries", &entries)
+            .field("slots", &self.values.len())
+            .field(
+                "bits_per_key",
+                &bits_per_key(self.function_bytes(), self.len()),
+            )
+            .finish()
+    }
+}
+
+/// Formats like a `HashMap`. The alternate form (`{:#?}`) also includes the number of shards
+/// and slots and the size of the perfect hash functions.
+impl<V> fmt::Debug for PhBytesMap<V>
+where
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = fmt::from_fn(|f| f.debug_map().entries(self.iter()).finish());
+
+        self.fmt_sharded("PhBytesMap", &entries, f)
+    }
+}
+
+/// Formats like [`PhBytesMap`], but with the keys as strings.
+impl<V> fmt::Debug for PhStrMap<V>
+where
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = fmt::from_fn(|f| f.debug_map().entries(self.iter()).finish());
+
+        self.inner.fmt_sharded("PhStrMap", &entries, f)
+    }
+}
+
+impl<V> PhBytesMap<V> {
+    fn fmt_sharded(
+        &self,
+        name: &str,
+        entries: &dyn fmt::Debug,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        if !f.alternate() {
+            return entries.fmt(f);
+        }
+
+        let stats = self.compression_stats();
+
+        f.debug_struct(name)
+            .field("entries", entries)
+            .field("shards", &stats.shards)
+            .field("slots", &stats.slots)
+            .field("bits_per_key", &stats.bits_per_key())
+            .finish()
+    }
+}
+
+impl<KOwned, V, KRef, H> PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    /// Size of the perfect hash function, including its heap allocations.
+    pub(crate) fn function_bytes(&self) -> usize {
+        self.to_index.size_bytes()
+    }
+}
+
+pub(crate) fn bits_per_key(function_bytes: usize, num_keys: usize) -> f64 {
+    if num_keys == 0 {
+        0.
+    } else {
+        (function_bytes * 8) as f64 / num_keys as f64
+    }
+}
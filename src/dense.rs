@@ -0,0 +1,59 @@
+use std::hash::Hash;
+
+use ph::BuildSeededHasher;
+
+use crate::{KeyHasher, PhMap};
+
+/// A map whose values are stored contiguously, with no holes, created by
+/// [`PhMap::compact_values`]. Each slot stores an index into the values, so lookups do one
+/// more indirection than a [`PhMap`], but the values can be processed as a single slice.
+pub struct PhDenseMap<KOwned, V, KRef = KOwned, H = KeyHasher>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    pub(crate) inner: PhMap<KOwned, u32, KRef, H>,
+    pub(crate) values: Vec<V>,
+}
+
+impl<KOwned, V, KRef, H> PhDenseMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    pub fn get<K>(&self, key: &K) -> Option<&V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = *self.inner.get(key)?;
+
+        Some(unsafe { self.values.get_unchecked(idx as usize) })
+    }
+
+    pub fn get_mut<K>(&mut self, key: &K) -> Option<&mut V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = *self.inner.get(key)?;
+
+        Some(unsafe { self.values.get_unchecked_mut(idx as usize) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// All values, in slot order.
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut [V] {
+        &mut self.values
+    }
+}
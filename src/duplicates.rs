@@ -0,0 +1,16 @@
+/// What a [`PhStrMap`](crate::PhStrMap) or [`PhBytesMap`](crate::PhBytesMap) does with a key
+/// which is added by `extend` when it's already in the map, or appears more than once in one
+/// batch. `insert` always replaces the value of an existing key instead. Set with
+/// [`MapOptions::duplicates`](crate::MapOptions::duplicates).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Duplicates {
+    /// Return [`BuildError::DuplicateKeys`](crate::BuildError::DuplicateKeys) without
+    /// changing the map, or panic from [`Extend::extend`].
+    #[default]
+    Error,
+    /// Keep the value which was inserted first, ignoring the later ones.
+    KeepFirst,
+    /// Keep the value which was inserted last. The key keeps the position in iteration order
+    /// of its first insertion.
+    KeepLast,
+}
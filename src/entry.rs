@@ -0,0 +1,215 @@
+use std::{collections::HashMap, hash::Hash};
+
+use ph::BuildSeededHasher;
+
+use crate::{KeyHasher, PhMap};
+
+/// A view into a single entry of a [`PhMap`], which may be either occupied or vacant.
+pub enum Entry<'a, KOwned, V, KRef = KOwned, H = KeyHasher>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    Occupied(OccupiedEntry<'a, KOwned, V, KRef, H>),
+    Vacant(VacantEntry<'a, KOwned, V, KRef, H>),
+}
+
+pub struct OccupiedEntry<'a, KOwned, V, KRef = KOwned, H = KeyHasher>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    pub(crate) key: KOwned,
+    pub(crate) idx: usize,
+    pub(crate) map: &'a mut PhMap<KOwned, V, KRef, H>,
+}
+
+pub struct VacantEntry<'a, KOwned, V, KRef = KOwned, H = KeyHasher>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    pub(crate) key: KOwned,
+    pub(crate) map: &'a mut PhMap<KOwned, V, KRef, H>,
+}
+
+impl<'a, KOwned, V, KRef, H> Entry<'a, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    pub fn key(&self) -> &KOwned {
+        match self {
+            Self::Occupied(entry) => entry.key(),
+            Self::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        if let Self::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+
+        self
+    }
+}
+
+impl<'a, KOwned, V, KRef, H> OccupiedEntry<'a, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    pub fn key(&self) -> &KOwned {
+        &self.key
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe {
+            self.map
+                .values
+                .get_unchecked(self.idx)
+                .as_ref()
+                .unwrap_unchecked()
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe {
+            self.map
+                .values
+                .get_unchecked_mut(self.idx)
+                .as_mut()
+                .unwrap_unchecked()
+        }
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe {
+            self.map
+                .values
+                .get_unchecked_mut(self.idx)
+                .as_mut()
+                .unwrap_unchecked()
+        }
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+}
+
+impl<'a, KOwned, V, KRef, H> VacantEntry<'a, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    pub fn key(&self) -> &KOwned {
+        &self.key
+    }
+
+    pub fn into_key(self) -> KOwned {
+        self.key
+    }
+
+    /// Inserts `value`, rebuilding the map.
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.map.insert_new(self.key, value)
+    }
+}
+
+/// Insertions staged against a [`PhMap`], created by [`PhMap::entry_batch`]. Keys already in
+/// the map are updated immediately, while new keys are held until [`EntryBatch::commit`]
+/// inserts them all with a single rebuild. Staged keys are discarded if the batch is dropped
+/// without being committed.
+#[must_use = "staged entries are discarded unless the batch is committed"]
+pub struct EntryBatch<'a, KOwned, V, KRef = KOwned, H = KeyHasher>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    pub(crate) map: &'a mut PhMap<KOwned, V, KRef, H>,
+    pub(crate) staged: Vec<(KOwned, u64, V)>,
+    /// Index into `staged` for each staged key's hash.
+    pub(crate) staged_by_hash: HashMap<u64, usize>,
+}
+
+impl<KOwned, V, KRef, H> EntryBatch<'_, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    pub fn or_insert(&mut self, key: KOwned, default: V) -> &mut V {
+        self.or_insert_with(key, || default)
+    }
+
+    pub fn or_insert_with<F>(&mut self, key: KOwned, default: F) -> &mut V
+    where
+        F: FnOnce() -> V,
+    {
+        let hash = PhMap::<KOwned, V, KRef, H>::hash_key(key.as_ref());
+
+        if let Some(idx) = self.map.find_index_prehashed(key.as_ref(), hash) {
+            return unsafe {
+                self.map
+                    .values
+                    .get_unchecked_mut(idx)
+                    .as_mut()
+                    .unwrap_unchecked()
+            };
+        }
+
+        // Like `PhMap`, keys are considered equal if their hashes are equal.
+        let staged_idx = *self.staged_by_hash.entry(hash).or_insert_with(|| {
+            self.staged.push((key, hash, default()));
+            self.staged.len() - 1
+        });
+
+        &mut self.staged[staged_idx].2
+    }
+
+    pub fn or_default(&mut self, key: KOwned) -> &mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(key, V::default)
+    }
+
+    /// Inserts the staged keys, rebuilding the map at most once.
+    pub fn commit(self) {
+        if !self.staged.is_empty() {
+            // Every staged hash was computed by `hash_key`.
+            unsafe { self.map.extend_prehashed(self.staged) };
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::marker::PhantomData;
+
+/// A key type with a fixed, known set of values, such as a fieldless enum. Each value maps to a
+/// distinct index below [`PhKey::COUNT`], so no hash function needs to be built. With the
+/// `derive` feature this can be derived, numbering variants in declaration order.
+pub trait PhKey: Sized {
+    const COUNT: usize;
+
+    fn index(&self) -> usize;
+
+    fn from_index(index: usize) -> Option<Self>;
+
+    /// A stable byte encoding of the key, independent of the platform and of the hasher.
+    fn to_bytes(&self) -> [u8; 4] {
+        (self.index() as u32).to_le_bytes()
+    }
+}
+
+/// A map keyed by a [`PhKey`], with one slot per possible key and so no holes.
+pub struct PhEnumMap<K, V> {
+    values: Box<[Option<V>]>,
+    _phantom: PhantomData<K>,
+}
+
+impl<K, V> Default for PhEnumMap<K, V>
+where
+    K: PhKey,
+{
+    fn default() -> Self {
+        Self {
+            values: (0..K::COUNT).map(|_| None).collect(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<K, V> PhEnumMap<K, V>
+where
+    K: PhKey,
+{
+    pub fn insert(&mut self, key: K, value: V) {
+        self.values[key.index()] = Some(value);
+    }
+
+    #[inline]
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.values.get(key.index())?.as_ref()
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.values.get_mut(key.index())?.as_mut()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(index, value)| Some((K::from_index(index)?, value.as_ref()?)))
+    }
+}
+
+impl<K, V> Extend<(K, V)> for PhEnumMap<K, V>
+where
+    K: PhKey,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for PhEnumMap<K, V>
+where
+    K: PhKey,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut out = Self::default();
+        out.extend(iter);
+        out
+    }
+}
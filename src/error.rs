@@ -0,0 +1,102 @@
+use std::{collections::TryReserveError, fmt};
+
+/// Errors which can occur while building the perfect hash function for a map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// Storage for the map's slots could not be allocated.
+    CapacityOverflow { slots: usize },
+    /// Maps can hold at most `u32::MAX` keys.
+    TooManyKeys { keys: usize },
+    /// The function was built, but doesn't place the key at `key_index` in insertion order.
+    /// This means a limit of the underlying PHast function was reached.
+    UnplacedKey { key_index: usize },
+    /// The key at `key_index` was placed in `slot`, as an earlier key was, because their
+    /// hashes collide. Only checked by maps built with
+    /// [`PhMapBuilder::verify_slots`](crate::PhMapBuilder::verify_slots).
+    SharedSlot { key_index: usize, slot: usize },
+    /// These keys were inserted more than once. Only returned by maps which can detect it,
+    /// such as [`PhBytesMap`](crate::PhBytesMap).
+    DuplicateKeys { keys: Vec<Vec<u8>> },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CapacityOverflow { slots } => {
+                write!(f, "could not allocate storage for {slots} slots")
+            }
+            Self::TooManyKeys { keys } => {
+                write!(f, "{keys} keys is more than the maximum of {}", u32::MAX)
+            }
+            Self::UnplacedKey { key_index } => {
+                write!(f, "the hash function did not place key {key_index}")
+            }
+            Self::SharedSlot { key_index, slot } => {
+                write!(
+                    f,
+                    "key {key_index} was placed in slot {slot}, as an earlier key was"
+                )
+            }
+            Self::DuplicateKeys { keys } => {
+                f.write_str("keys inserted more than once: ")?;
+                f.debug_list()
+                    .entries(keys.iter().map(|key| String::from_utf8_lossy(key)))
+                    .finish()
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl BuildError {
+    pub(crate) fn capacity_overflow(slots: usize) -> impl FnOnce(TryReserveError) -> Self {
+        move |_| Self::CapacityOverflow { slots }
+    }
+}
+
+/// Inconsistencies found by [`PhMap::verify`](crate::PhMap::verify).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The value and hash tables have different lengths.
+    LengthMismatch { values: usize, hashes: usize },
+    /// The key at `key_index` doesn't map to a slot within the map.
+    MissingSlot { key_index: usize },
+    /// The key at `key_index` maps to `slot`, which is empty or stores a different hash.
+    SlotMismatch { key_index: usize, slot: usize },
+    /// [`FrozenPhMap::thaw`](crate::FrozenPhMap::thaw) was given `keys` keys for a map with
+    /// `len` entries.
+    KeyCountMismatch { keys: usize, len: usize },
+    /// The key at `key_index` maps to `slot`, which an earlier key also maps to.
+    DuplicateKey { key_index: usize, slot: usize },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LengthMismatch { values, hashes } => {
+                write!(f, "map has {values} value slots but {hashes} hash slots")
+            }
+            Self::MissingSlot { key_index } => {
+                write!(f, "key {key_index} does not map to a slot")
+            }
+            Self::KeyCountMismatch { keys, len } => {
+                write!(f, "given {keys} keys for a map with {len} entries")
+            }
+            Self::DuplicateKey { key_index, slot } => {
+                write!(
+                    f,
+                    "key {key_index} maps to slot {slot}, as an earlier key does"
+                )
+            }
+            Self::SlotMismatch { key_index, slot } => {
+                write!(
+                    f,
+                    "key {key_index} maps to slot {slot}, which does not match it"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
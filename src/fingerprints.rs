@@ -0,0 +1,110 @@
+use std::collections::TryReserveError;
+
+/// Applies `$body` to whichever `Vec` holds the fingerprints.
+macro_rules! with_vec {
+    ($fingerprints:expr, $vec:ident => $body:expr) => {
+        match $fingerprints {
+            Fingerprints::U16($vec) => $body,
+            Fingerprints::U32($vec) => $body,
+            Fingerprints::U64($vec) => $body,
+        }
+    };
+}
+
+/// The low bits of the hash of the key in each slot, which lookups compare against the hash
+/// of the key they're looking for. A key which isn't in the map is only found if its
+/// fingerprint matches, so narrower fingerprints save memory at the cost of more false
+/// positives. The width is chosen by the map's [`Profile`](crate::Profile).
+#[derive(Debug, Clone)]
+pub(crate) enum Fingerprints {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+}
+
+impl Default for Fingerprints {
+    fn default() -> Self {
+        Self::U64(vec![])
+    }
+}
+
+impl Fingerprints {
+    /// Fingerprints of at least `bits` bits, up to 64.
+    pub(crate) fn new(bits: u8) -> Self {
+        match bits {
+            ..=16 => Self::U16(vec![]),
+            17..=32 => Self::U32(vec![]),
+            _ => Self::U64(vec![]),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        with_vec!(self, vec => vec.len())
+    }
+
+    pub(crate) fn size_bytes(&self) -> usize {
+        with_vec!(self, vec => capacity_bytes(vec))
+    }
+
+    /// Whether `idx` is a slot and its fingerprint matches `hash`.
+    #[inline]
+    pub(crate) fn matches(&self, idx: usize, hash: u64) -> bool {
+        with_vec!(self, vec => vec.get(idx).is_some_and(|&stored| stored == hash as _))
+    }
+
+    /// The fingerprint of slot `idx`, widened to 64 bits.
+    pub(crate) fn get(&self, idx: usize) -> Option<u64> {
+        match self {
+            Self::U16(vec) => vec.get(idx).map(|&stored| stored.into()),
+            Self::U32(vec) => vec.get(idx).map(|&stored| stored.into()),
+            Self::U64(vec) => vec.get(idx).copied(),
+        }
+    }
+
+    /// Every fingerprint, widened to 64 bits, in slot order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len()).filter_map(|idx| self.get(idx))
+    }
+
+    /// A pointer to the fingerprint of slot `idx`, for prefetching. It may be dangling if
+    /// `idx` isn't a slot.
+    pub(crate) fn ptr_at(&self, idx: usize) -> *const u8 {
+        with_vec!(self, vec => vec.as_ptr().wrapping_add(idx).cast())
+    }
+
+    /// Sets every fingerprint to zero and resizes to `num_slots`.
+    pub(crate) fn reset(&mut self, num_slots: usize) {
+        with_vec!(self, vec => {
+            vec.clear();
+            vec.resize(num_slots, 0);
+        })
+    }
+
+    /// Sets the fingerprint of slot `idx` from `hash`.
+    ///
+    /// # Safety
+    /// `idx` must be below the number of slots.
+    pub(crate) unsafe fn set_unchecked(&mut self, idx: usize, hash: u64) {
+        with_vec!(self, vec => unsafe { *vec.get_unchecked_mut(idx) = hash as _ })
+    }
+
+    pub(crate) fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        with_vec!(self, vec => vec.try_reserve_exact(additional))
+    }
+
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        with_vec!(self, vec => vec.reserve(additional))
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        with_vec!(self, vec => vec.shrink_to_fit())
+    }
+
+    pub(crate) fn clear(&mut self) {
+        with_vec!(self, vec => vec.clear())
+    }
+}
+
+fn capacity_bytes<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * size_of::<T>()
+}
@@ -0,0 +1,203 @@
+use std::{fmt, hash::Hash, marker::PhantomData};
+
+use ph::BuildSeededHasher;
+
+use crate::{
+    IntegrityError, KeyHasher, PerfectMap, PhMap, Profile, find_slot, fingerprints::Fingerprints,
+    occupancy::Occupancy, slot_function::SlotFunction,
+};
+
+/// A read-only map which doesn't keep its keys, created by [`PhMap::freeze`] or by collecting
+/// an iterator. Lookups only ever compared hashes, so they behave exactly as in the [`PhMap`]
+/// it was built from, but the owned keys and their per-slot indices are dropped. This saves
+/// memory for large tables whose keys are never iterated over.
+pub struct FrozenPhMap<K, V>
+where
+    K: ?Sized + Hash,
+{
+    top_level_hashes: Fingerprints,
+    values: Box<[Option<V>]>,
+    occupied: Occupancy,
+    to_index: SlotFunction,
+    profile: Profile,
+    len: usize,
+    _phantom: PhantomData<fn(&K)>,
+}
+
+impl<KOwned, V, KRef> PhMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    /// Drops the keys, keeping only what lookups need.
+    pub fn freeze(mut self) -> FrozenPhMap<KRef, V> {
+        self.top_level_hashes.shrink_to_fit();
+
+        FrozenPhMap {
+            len: self.len(),
+            top_level_hashes: self.top_level_hashes,
+            values: self.values.into(),
+            occupied: self.occupied,
+            to_index: self.to_index,
+            profile: self.profile,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<K, V> FrozenPhMap<K, V>
+where
+    K: ?Sized + Hash,
+{
+    #[inline]
+    fn find_index(&self, key: &K) -> Option<usize> {
+        let hash = KeyHasher::default().hash_one(key, 0);
+
+        find_slot(
+            &self.to_index,
+            &self.top_level_hashes,
+            &self.occupied,
+            key,
+            hash,
+        )
+    }
+
+    /// Turns this back into a [`PhMap`] by giving it the keys which were dropped, without
+    /// rebuilding the function. Every key of the map must be given exactly once, in the order
+    /// they'll be iterated in. On error, the map is dropped.
+    pub fn thaw<KOwned, I>(self, keys: I) -> Result<PhMap<KOwned, V, K>, IntegrityError>
+    where
+        KOwned: AsRef<K>,
+        I: IntoIterator<Item = KOwned>,
+    {
+        let keys = keys.into_iter().collect::<Vec<_>>();
+        if keys.len() != self.len {
+            return Err(IntegrityError::KeyCountMismatch {
+                keys: keys.len(),
+                len: self.len,
+            });
+        }
+
+        // Every key is in a different slot, so there are fewer keys than `u32::MAX`.
+        let mut key_indices = vec![u32::MAX; self.values.len()];
+        for (key_index, key) in keys.iter().enumerate() {
+            let slot = self
+                .find_index(key.as_ref())
+                .ok_or(IntegrityError::MissingSlot { key_index })?;
+            if key_indices[slot] != u32::MAX {
+                return Err(IntegrityError::DuplicateKey { key_index, slot });
+            }
+
+            key_indices[slot] = key_index as u32;
+        }
+
+        // Empty slots are never read through `key_indices`.
+        for index in &mut key_indices {
+            if *index == u32::MAX {
+                *index = 0;
+            }
+        }
+
+        Ok(PhMap {
+            keys,
+            top_level_hashes: self.top_level_hashes,
+            values: self.values.into(),
+            occupied: self.occupied,
+            key_indices,
+            to_index: self.to_index,
+            ..PhMap::with_profile(self.profile)
+        })
+    }
+
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: ?Sized + AsRef<K>,
+    {
+        self.values.get(self.find_index(key.as_ref())?)?.as_ref()
+    }
+
+    #[inline]
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        Q: ?Sized + AsRef<K>,
+    {
+        let idx = self.find_index(key.as_ref())?;
+
+        self.values.get_mut(idx)?.as_mut()
+    }
+
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: ?Sized + AsRef<K>,
+    {
+        self.find_index(key.as_ref()).is_some()
+    }
+
+    /// Iterates over the values in slot order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.values.iter().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<K, V> PerfectMap<K> for FrozenPhMap<K, V>
+where
+    K: ?Sized + Hash,
+{
+    type Value = V;
+    type Values<'a>
+        = std::iter::Flatten<std::slice::Iter<'a, Option<V>>>
+    where
+        Self: 'a;
+
+    fn get(&self, key: &K) -> Option<&V> {
+        self.values.get(self.find_index(key)?)?.as_ref()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.find_index(key).is_some()
+    }
+
+    fn values(&self) -> Self::Values<'_> {
+        self.values.iter().flatten()
+    }
+}
+
+/// Builds a [`PhMap`] from the entries and freezes it, so the keys are dropped as soon as the
+/// map is built.
+impl<KOwned, V, KRef> FromIterator<(KOwned, V)> for FrozenPhMap<KRef, V>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (KOwned, V)>,
+    {
+        kvs.into_iter().collect::<PhMap<KOwned, V, KRef>>().freeze()
+    }
+}
+
+/// Formats like a list of the values, as the keys aren't kept.
+impl<K, V> fmt::Debug for FrozenPhMap<K, V>
+where
+    K: ?Sized + Hash,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.values()).finish()
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    collections::{HashMap, hash_map},
+    fmt,
+    hash::Hash,
+};
+
+use crate::PhMap;
+
+/// A map which keeps its entries in insertion order, like `indexmap`'s `IndexMap`, with
+/// perfect hash lookups. Keys and values are stored densely in insertion order, and each key's
+/// slot holds its index.
+pub struct PhIndexMap<KOwned, V, KRef = KOwned>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    /// `indices.keys` is in insertion order, as new keys are always appended.
+    indices: PhMap<KOwned, u32, KRef>,
+    values: Vec<V>,
+}
+
+impl<KOwned, V, KRef> Default for PhIndexMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn default() -> Self {
+        Self {
+            indices: PhMap::default(),
+            values: vec![],
+        }
+    }
+}
+
+impl<KOwned, V, KRef> PhIndexMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    /// Inserts `value`, returning the old value if `key` was already in the map. The key
+    /// keeps its index if it was, and is added at the end otherwise.
+    pub fn insert(&mut self, key: KOwned, value: V) -> Option<V> {
+        if let Some(old) = self.get_mut(&key) {
+            return Some(std::mem::replace(old, value));
+        }
+
+        self.extend(std::iter::once((key, value)));
+        None
+    }
+
+    #[inline]
+    pub fn get<K>(&self, key: &K) -> Option<&V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.values.get(self.get_index_of(key)?)
+    }
+
+    #[inline]
+    pub fn get_mut<K>(&mut self, key: &K) -> Option<&mut V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = self.get_index_of(key)?;
+
+        self.values.get_mut(idx)
+    }
+
+    /// The index of `key` in insertion order.
+    #[inline]
+    pub fn get_index_of<K>(&self, key: &K) -> Option<usize>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.indices.get(key).map(|idx| *idx as usize)
+    }
+
+    /// The index of `key` in insertion order, the stored key and its value.
+    pub fn get_full<K>(&self, key: &K) -> Option<(usize, &KOwned, &V)>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = self.get_index_of(key)?;
+        let (key, value) = self.get_index(idx)?;
+
+        Some((idx, key, value))
+    }
+
+    /// The entry at `idx` in insertion order.
+    pub fn get_index(&self, idx: usize) -> Option<(&KOwned, &V)> {
+        Some((self.indices.keys.get(idx)?, self.values.get(idx)?))
+    }
+
+    pub fn get_index_mut(&mut self, idx: usize) -> Option<(&KOwned, &mut V)> {
+        Some((self.indices.keys.get(idx)?, self.values.get_mut(idx)?))
+    }
+
+    #[inline]
+    pub fn contains_key<K>(&self, key: &K) -> bool
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.indices.contains_key(key)
+    }
+
+    /// Iterates over the entries in insertion order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&KOwned, &V)> + ExactSizeIterator {
+        self.indices.keys.iter().zip(&self.values)
+    }
+
+    pub fn keys(&self) -> &[KOwned] {
+        &self.indices.keys
+    }
+
+    /// All values, in insertion order.
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut [V] {
+        &mut self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// Keys which are already in the map keep their index and get the new value. New keys are
+/// added at the end, with a single rebuild.
+impl<KOwned, V, KRef> Extend<(KOwned, V)> for PhIndexMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (KOwned, V)>,
+    {
+        let mut new = vec![];
+        let mut new_by_hash = HashMap::new();
+
+        for (key, value) in kv {
+            if let Some(old) = self.get_mut(&key) {
+                *old = value;
+                continue;
+            }
+
+            // New keys with the same hash are the same key, as the map couldn't tell them
+            // apart anyway.
+            let hash = PhMap::<KOwned, u32, KRef>::hash_key(key.as_ref());
+            match new_by_hash.entry(hash) {
+                hash_map::Entry::Occupied(idx) => self.values[*idx.get()] = value,
+                hash_map::Entry::Vacant(entry) => {
+                    let idx = self.values.len();
+                    let idx_u32 = idx.try_into().expect("more than `u32::MAX` entries");
+
+                    entry.insert(idx);
+                    new.push((key, hash, idx_u32));
+                    self.values.push(value);
+                }
+            }
+        }
+
+        if !new.is_empty() {
+            // Every hash was computed by `hash_key` for its key.
+            unsafe { self.indices.extend_prehashed(new) };
+        }
+    }
+}
+
+impl<KOwned, V, KRef> FromIterator<(KOwned, V)> for PhIndexMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (KOwned, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<KOwned, V, KRef> fmt::Debug for PhIndexMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef> + fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
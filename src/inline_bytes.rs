@@ -0,0 +1,58 @@
+/// Keys up to this many bytes are stored inline rather than on the heap.
+pub(crate) const INLINE_CAPACITY: usize = 23;
+
+/// A byte string which doesn't allocate if it's at most [`INLINE_CAPACITY`] bytes long or
+/// borrowed for `'static`.
+#[derive(Debug, Clone)]
+pub(crate) enum InlineBytes {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_CAPACITY],
+    },
+    Heap(Box<[u8]>),
+    Static(&'static [u8]),
+}
+
+impl From<&[u8]> for InlineBytes {
+    fn from(value: &[u8]) -> Self {
+        if value.len() <= INLINE_CAPACITY {
+            let mut bytes = [0; INLINE_CAPACITY];
+            bytes[..value.len()].copy_from_slice(value);
+
+            Self::Inline {
+                len: value.len() as u8,
+                bytes,
+            }
+        } else {
+            Self::Heap(value.into())
+        }
+    }
+}
+
+impl From<Vec<u8>> for InlineBytes {
+    /// Reuses `value`'s allocation if it's too long to store inline.
+    fn from(value: Vec<u8>) -> Self {
+        if value.len() <= INLINE_CAPACITY {
+            Self::from(&value[..])
+        } else {
+            Self::Heap(value.into_boxed_slice())
+        }
+    }
+}
+
+impl AsRef<[u8]> for InlineBytes {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Inline { len, bytes } => {
+                debug_assert!(*len as usize <= INLINE_CAPACITY);
+
+                // SAFETY: inline keys are only created by `From<&[u8]>`, which stores at most
+                // `INLINE_CAPACITY` bytes inline, so `len` is in bounds of `bytes`.
+                unsafe { bytes.get_unchecked(..*len as usize) }
+            }
+            Self::Heap(bytes) => bytes,
+            Self::Static(bytes) => bytes,
+        }
+    }
+}
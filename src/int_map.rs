@@ -0,0 +1,314 @@
+use std::{
+    collections::{HashMap, hash_map},
+    fmt,
+    marker::PhantomData,
+};
+
+use crate::{Function, Profile, build_function};
+
+/// An integer type which can be used as the key of a [`PhIntMap`].
+pub trait IntKey: Copy {
+    /// Must give a distinct value for each key, and should preserve their order, so that
+    /// consecutive keys are stored densely.
+    fn to_u64(self) -> u64;
+
+    fn from_u64(value: u64) -> Self;
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl IntKey for $t {
+                #[inline]
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+
+                #[inline]
+                fn from_u64(value: u64) -> Self {
+                    value as Self
+                }
+            }
+        )*
+    };
+}
+
+/// Flips the sign bit, so that negative keys sort before positive ones.
+macro_rules! impl_signed {
+    ($($t:ty),*) => {
+        $(
+            impl IntKey for $t {
+                #[inline]
+                fn to_u64(self) -> u64 {
+                    (self as i64 as u64) ^ (1 << 63)
+                }
+
+                #[inline]
+                fn from_u64(value: u64) -> Self {
+                    (value ^ (1 << 63)) as i64 as Self
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned!(u8, u16, u32, u64, usize);
+impl_signed!(i8, i16, i32, i64, isize);
+
+/// Keys are stored in an array indexed by the key if they span at most this many times as many
+/// values as there are keys.
+const MAX_DENSE_SPAN_PER_KEY: u64 = 2;
+
+/// A map keyed by integers. If the keys are mostly consecutive the values are stored in an
+/// array indexed by the key, with no hash function at all. Otherwise each key is hashed
+/// directly as a `u64`, and its slot stores the key itself instead of a hash, so lookups
+/// compare the key exactly and never need to hash it twice.
+pub struct PhIntMap<K, V> {
+    layout: Layout<V>,
+    len: usize,
+    _phantom: PhantomData<fn(K)>,
+}
+
+enum Layout<V> {
+    /// The value of key `min + i` is in slot `i`.
+    Dense { min: u64, slots: Vec<Option<V>> },
+    Sparse {
+        to_index: Function,
+        slots: Vec<Option<(u64, V)>>,
+    },
+}
+
+impl<K, V> Default for PhIntMap<K, V> {
+    fn default() -> Self {
+        Self {
+            layout: Layout::Dense {
+                min: 0,
+                slots: vec![],
+            },
+            len: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<K, V> PhIntMap<K, V>
+where
+    K: IntKey,
+{
+    /// Inserts `value`, returning the old value if `key` was already in the map. Only
+    /// inserting a new key rebuilds the map.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(old) = self.get_mut(key) {
+            return Some(std::mem::replace(old, value));
+        }
+
+        self.extend(std::iter::once((key, value)));
+        None
+    }
+
+    #[inline]
+    pub fn get(&self, key: K) -> Option<&V> {
+        let key = key.to_u64();
+
+        match &self.layout {
+            Layout::Dense { min, slots } => slots
+                .get(usize::try_from(key.wrapping_sub(*min)).ok()?)?
+                .as_ref(),
+            Layout::Sparse { to_index, slots } => {
+                let (stored, value) = slots.get(to_index.get(&key)?)?.as_ref()?;
+
+                (*stored == key).then_some(value)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        let key = key.to_u64();
+
+        match &mut self.layout {
+            Layout::Dense { min, slots } => slots
+                .get_mut(usize::try_from(key.wrapping_sub(*min)).ok()?)?
+                .as_mut(),
+            Layout::Sparse { to_index, slots } => {
+                let (stored, value) = slots.get_mut(to_index.get(&key)?)?.as_mut()?;
+
+                (*stored == key).then_some(value)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn contains_key(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes `key` without rebuilding, leaving its slot empty.
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let key = key.to_u64();
+
+        let removed = match &mut self.layout {
+            Layout::Dense { min, slots } => slots
+                .get_mut(usize::try_from(key.wrapping_sub(*min)).ok()?)?
+                .take(),
+            Layout::Sparse { to_index, slots } => {
+                let slot = slots.get_mut(to_index.get(&key)?)?;
+                if slot.as_ref()?.0 != key {
+                    return None;
+                }
+
+                slot.take().map(|(_, value)| value)
+            }
+        };
+        self.len -= removed.is_some() as usize;
+
+        removed
+    }
+
+    /// Whether the values are stored in an array indexed by the key, rather than by a
+    /// perfect hash function.
+    pub fn is_dense(&self) -> bool {
+        matches!(self.layout, Layout::Dense { .. })
+    }
+
+    /// Iterates over the entries in slot order. For a dense map, this is ascending key order.
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        let (dense, sparse) = match &self.layout {
+            Layout::Dense { min, slots } => (Some((*min, slots)), None),
+            Layout::Sparse { slots, .. } => (None, Some(slots)),
+        };
+
+        let dense = dense.into_iter().flat_map(|(min, slots)| {
+            slots.iter().enumerate().filter_map(move |(i, value)| {
+                let key = K::from_u64(min + i as u64);
+
+                Some((key, value.as_ref()?))
+            })
+        });
+        let sparse = sparse
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|(key, value)| (K::from_u64(*key), value));
+
+        dense.chain(sparse)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Rebuilds the map over `entries`, whose keys must be distinct.
+    #[cold]
+    #[inline(never)]
+    fn rebuild(&mut self, entries: Vec<(u64, V)>) {
+        self.len = entries.len();
+
+        let min = entries.iter().map(|(key, _)| *key).min().unwrap_or(0);
+        let max = entries.iter().map(|(key, _)| *key).max().unwrap_or(0);
+        let span = max - min;
+
+        if span < (entries.len() as u64).saturating_mul(MAX_DENSE_SPAN_PER_KEY) {
+            let mut slots = Vec::new();
+            slots.resize_with(span as usize + 1, || None);
+            for (key, value) in entries {
+                slots[(key - min) as usize] = Some(value);
+            }
+
+            self.layout = Layout::Dense { min, slots };
+            return;
+        }
+
+        let to_index = build_function(
+            entries.iter().map(|(key, _)| *key).collect(),
+            Profile::default(),
+        );
+        let indices = entries
+            .iter()
+            .map(|(key, _)| to_index.get(key).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut slots = Vec::new();
+        slots.resize_with(indices.iter().max().map_or(0, |idx| idx + 1), || None);
+        for (idx, entry) in indices.into_iter().zip(entries) {
+            slots[idx] = Some(entry);
+        }
+
+        self.layout = Layout::Sparse { to_index, slots };
+    }
+
+    fn take_entries(&mut self) -> Vec<(u64, V)> {
+        match std::mem::take(self).layout {
+            Layout::Dense { min, slots } => slots
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, value)| Some((min + i as u64, value?)))
+                .collect(),
+            Layout::Sparse { slots, .. } => slots.into_iter().flatten().collect(),
+        }
+    }
+}
+
+/// Keys which are already in the map get the new value. New keys are added with a single
+/// rebuild, with later values replacing earlier ones for keys which appear more than once.
+impl<K, V> Extend<(K, V)> for PhIntMap<K, V>
+where
+    K: IntKey,
+{
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (K, V)>,
+    {
+        let mut new = vec![];
+        let mut new_by_key = HashMap::new();
+
+        for (key, value) in kv {
+            if let Some(old) = self.get_mut(key) {
+                *old = value;
+                continue;
+            }
+
+            match new_by_key.entry(key.to_u64()) {
+                hash_map::Entry::Occupied(idx) => new[*idx.get()] = (key.to_u64(), value),
+                hash_map::Entry::Vacant(entry) => {
+                    entry.insert(new.len());
+                    new.push((key.to_u64(), value));
+                }
+            }
+        }
+
+        if !new.is_empty() {
+            let mut entries = self.take_entries();
+            entries.extend(new);
+            self.rebuild(entries);
+        }
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for PhIntMap<K, V>
+where
+    K: IntKey,
+{
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<K, V> fmt::Debug for PhIntMap<K, V>
+where
+    K: IntKey + fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash, RandomState},
+};
+
+use crate::PhMap;
+
+/// A map which stores each distinct value once, with each key storing an index into the
+/// table of distinct values. This is useful when many keys share a small set of values.
+pub struct PhInternedMap<KOwned, V, KRef = KOwned>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    inner: PhMap<KOwned, u32, KRef>,
+    values: Vec<V>,
+    /// The number of keys using each value in `values`.
+    uses: Vec<u32>,
+}
+
+impl<KOwned, V, KRef> Default for PhInternedMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn default() -> Self {
+        Self {
+            inner: Default::default(),
+            values: vec![],
+            uses: vec![],
+        }
+    }
+}
+
+impl<KOwned, V, KRef> PhInternedMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    V: Hash + Eq,
+{
+    /// Inserts `value` for `key`, replacing the value if `key` is already in the map. The map
+    /// is only rebuilt if `key` is new.
+    pub fn insert(&mut self, key: KOwned, value: V) {
+        self.extend(std::iter::once((key, value)))
+    }
+
+    pub fn get<K>(&self, key: &K) -> Option<&V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = *self.inner.get(key)?;
+
+        Some(unsafe { self.values.get_unchecked(idx as usize) })
+    }
+
+    /// All distinct values in the map, in the order they were first inserted.
+    pub fn distinct_values(&self) -> &[V] {
+        &self.values
+    }
+}
+
+impl<KOwned, V, KRef> Extend<(KOwned, V)> for PhInternedMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    V: Hash + Eq,
+{
+    /// Keys which are already in the map have their value replaced in place. Values which are
+    /// no longer used by any key are dropped.
+    fn extend<I>(&mut self, kv: I)
+    where
+        I: IntoIterator<Item = (KOwned, V)>,
+    {
+        let build_hasher = RandomState::new();
+
+        let mut indices_by_hash = HashMap::<u64, Vec<u32>>::new();
+        for (idx, value) in self.values.iter().enumerate() {
+            indices_by_hash
+                .entry(build_hasher.hash_one(value))
+                .or_default()
+                .push(idx as u32);
+        }
+
+        let mut new_entries = vec![];
+        let mut released = false;
+        for (key, value) in kv {
+            let candidates = indices_by_hash
+                .entry(build_hasher.hash_one(&value))
+                .or_default();
+
+            let idx = match candidates
+                .iter()
+                .find(|idx| self.values[**idx as usize] == value)
+            {
+                Some(idx) => *idx,
+                None => {
+                    let idx = self
+                        .values
+                        .len()
+                        .try_into()
+                        .expect("more than `u32::MAX` distinct values");
+                    self.values.push(value);
+                    self.uses.push(0);
+                    candidates.push(idx);
+                    idx
+                }
+            };
+            self.uses[idx as usize] += 1;
+
+            match self.inner.get_mut(&key) {
+                Some(old_idx) => {
+                    let old_idx = std::mem::replace(old_idx, idx);
+                    self.uses[old_idx as usize] -= 1;
+                    released |= self.uses[old_idx as usize] == 0;
+                }
+                None => new_entries.push((key, idx)),
+            }
+        }
+
+        if released {
+            self.drop_unused(&mut new_entries);
+        }
+        if !new_entries.is_empty() {
+            self.inner.extend(new_entries);
+        }
+    }
+}
+
+impl<KOwned, V, KRef> PhInternedMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    /// Removes the values which no key uses, and renumbers the indices of the rest, including
+    /// those of `pending` entries which haven't been added to `inner` yet.
+    fn drop_unused(&mut self, pending: &mut [(KOwned, u32)]) {
+        let mut new_indices = Vec::with_capacity(self.values.len());
+        let mut next = 0;
+        for &uses in &self.uses {
+            new_indices.push(next);
+            next += (uses > 0) as u32;
+        }
+
+        let mut uses = self.uses.iter();
+        self.values
+            .retain(|_| uses.next().is_some_and(|&uses| uses > 0));
+        self.uses.retain(|&uses| uses > 0);
+
+        for idx in self
+            .inner
+            .values
+            .iter_mut()
+            .flatten()
+            .chain(pending.iter_mut().map(|(_, idx)| idx))
+        {
+            *idx = new_indices[*idx as usize];
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{MapOptions, PhStrMap};
+
+/// A handle to a string in a [`PhStrInterner`], which can be resolved back to the string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyId(u32);
+
+impl KeyId {
+    /// The number of strings interned before this one.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A string interner with perfect hash lookups, handing out a [`KeyId`] for each distinct
+/// string. Each string is stored once, in a [`PhStrMap`] which maps it to its id, and ids are
+/// resolved by their key's length and position among the keys of that length.
+///
+/// Every new string rebuilds the function of its length's shard, so intern strings in bulk
+/// with [`Extend`] where possible.
+#[derive(Clone)]
+pub struct PhStrInterner {
+    ids: PhStrMap<KeyId>,
+    /// The length of each interned string, and its position among the strings of that length.
+    positions: Vec<(u32, u32)>,
+}
+
+impl Default for PhStrInterner {
+    fn default() -> Self {
+        Self {
+            ids: PhStrMap::with_options(MapOptions::new().verify_keys(true)),
+            positions: vec![],
+        }
+    }
+}
+
+impl PhStrInterner {
+    /// The id of `key`, interning it if it hasn't been already.
+    pub fn intern(&mut self, key: &str) -> KeyId {
+        if let Some(id) = self.get(key) {
+            return id;
+        }
+
+        self.intern_new(vec![Cow::Owned(key.to_owned())]);
+        KeyId(self.positions.len() as u32 - 1)
+    }
+
+    #[inline]
+    pub fn get<K>(&self, key: &K) -> Option<KeyId>
+    where
+        K: ?Sized + AsRef<str>,
+    {
+        self.ids.get(key).copied()
+    }
+
+    /// The string `id` was handed out for.
+    ///
+    /// # Panics
+    /// If `id` came from a different interner.
+    pub fn resolve(&self, id: KeyId) -> &str {
+        let (len, position) = self.positions[id.index()];
+        let key = self
+            .ids
+            .inner
+            .key_of_len(len as usize, position as usize)
+            .expect("KeyId from a different interner");
+
+        // Keys are only inserted as `str`s.
+        unsafe { std::str::from_utf8_unchecked(key) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Iterates over the interned strings in the order they were interned.
+    pub fn iter(&self) -> impl Iterator<Item = (KeyId, &str)> {
+        (0..self.positions.len() as u32).map(|id| (KeyId(id), self.resolve(KeyId(id))))
+    }
+
+    /// Interns `keys`, which must be distinct and not already interned, with a single
+    /// rebuild per key length.
+    fn intern_new(&mut self, keys: Vec<Cow<'static, str>>) {
+        let first_id = self.positions.len();
+
+        // Keys of each length are appended in order, after the keys of that length which are
+        // already in the map.
+        let mut next_positions = HashMap::new();
+        for key in &keys {
+            let position = next_positions
+                .entry(key.len())
+                .or_insert_with(|| self.ids.inner.count_of_len(key.len()));
+
+            self.positions.push((
+                key.len().try_into().unwrap(),
+                (*position).try_into().unwrap(),
+            ));
+            *position += 1;
+        }
+
+        self.ids.extend(
+            keys.into_iter()
+                .zip(first_id..)
+                .map(|(key, id)| (key, KeyId(id.try_into().unwrap()))),
+        );
+    }
+
+    fn extend_cow<I>(&mut self, keys: I)
+    where
+        I: IntoIterator<Item = Cow<'static, str>>,
+    {
+        let mut seen = HashSet::new();
+        let new = keys
+            .into_iter()
+            .filter(|key| self.get(key).is_none() && seen.insert(key.clone()))
+            .collect::<Vec<_>>();
+
+        self.intern_new(new);
+    }
+}
+
+/// Strings which are already interned, or appear more than once, are only interned once.
+impl Extend<String> for PhStrInterner {
+    fn extend<I>(&mut self, keys: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.extend_cow(keys.into_iter().map(Cow::Owned))
+    }
+}
+
+/// Borrows the strings rather than copying them, like the equivalent impl for [`PhStrMap`].
+impl Extend<&'static str> for PhStrInterner {
+    fn extend<I>(&mut self, keys: I)
+    where
+        I: IntoIterator<Item = &'static str>,
+    {
+        self.extend_cow(keys.into_iter().map(Cow::Borrowed))
+    }
+}
+
+impl fmt::Debug for PhStrInterner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash, RandomState},
+};
+
+use ph::BuildSeededHasher;
+
+use crate::{KeyHasher, PhMap};
+
+/// Reverse lookup from values to the keys that map to them, created by
+/// [`PhMap::inverse_index`]. To build the reverse lookup along with the map, use
+/// [`PhInverseMap`].
+pub struct InverseIndex<'a, KOwned, V> {
+    keys_by_value: HashMap<&'a V, Vec<&'a KOwned>>,
+}
+
+impl<'a, KOwned, V> InverseIndex<'a, KOwned, V>
+where
+    V: Hash + Eq,
+{
+    pub(crate) fn new<KRef, H>(map: &'a PhMap<KOwned, V, KRef, H>) -> Self
+    where
+        KRef: ?Sized + Hash,
+        KOwned: AsRef<KRef>,
+        H: BuildSeededHasher + Default,
+    {
+        let mut keys_by_value = HashMap::<_, Vec<_>>::new();
+
+        for (_, key, value) in map.iter_indexed() {
+            keys_by_value.entry(value).or_default().push(key);
+        }
+
+        Self { keys_by_value }
+    }
+
+    /// All keys which map to `value`, in no particular order.
+    pub fn keys_for_value(&self, value: &V) -> &[&'a KOwned] {
+        self.keys_by_value
+            .get(value)
+            .map(|keys| &keys[..])
+            .unwrap_or_default()
+    }
+
+    /// The number of distinct values in the map.
+    pub fn distinct_values(&self) -> usize {
+        self.keys_by_value.len()
+    }
+}
+
+/// A [`PhMap`] which keeps a reverse lookup from values to keys, built along with the map
+/// rather than on request like [`InverseIndex`], and rebuilt whenever entries are added. Values
+/// can't be changed in place, as that would leave the reverse lookup out of date.
+pub struct PhInverseMap<KOwned, V, KRef = KOwned, H = KeyHasher>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    map: PhMap<KOwned, V, KRef, H>,
+    /// The slots of the entries with each value, keyed by the hash of the value. Values whose
+    /// hashes collide share a list, so lookups compare the value in each slot.
+    slots_by_value: HashMap<u64, Vec<usize>>,
+    value_hasher: RandomState,
+}
+
+impl<KOwned, V, KRef, H> PhInverseMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    V: Hash + Eq,
+    H: BuildSeededHasher + Default,
+{
+    /// Indexes every entry of `map` by its value.
+    pub fn new(map: PhMap<KOwned, V, KRef, H>) -> Self {
+        let mut inverse = Self {
+            map,
+            slots_by_value: HashMap::new(),
+            value_hasher: RandomState::new(),
+        };
+        inverse.reindex();
+
+        inverse
+    }
+
+    fn reindex(&mut self) {
+        self.slots_by_value.clear();
+
+        for (slot, _, value) in self.map.iter_indexed() {
+            self.slots_by_value
+                .entry(self.value_hasher.hash_one(value))
+                .or_default()
+                .push(slot);
+        }
+    }
+
+    #[inline]
+    pub fn get<K>(&self, key: &K) -> Option<&V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.map.get(key)
+    }
+
+    /// All keys which map to `value`, in no particular order.
+    pub fn keys_for_value<'a>(&'a self, value: &'a V) -> impl Iterator<Item = &'a KOwned> {
+        self.slots_by_value
+            .get(&self.value_hasher.hash_one(value))
+            .into_iter()
+            .flatten()
+            .filter_map(move |&slot| {
+                let (key, stored) = self.map.get_by_index(slot)?;
+
+                (stored == value).then_some(key)
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn as_map(&self) -> &PhMap<KOwned, V, KRef, H> {
+        &self.map
+    }
+
+    pub fn into_map(self) -> PhMap<KOwned, V, KRef, H> {
+        self.map
+    }
+}
+
+impl<KOwned, V, KRef, H> Extend<(KOwned, V)> for PhInverseMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    V: Hash + Eq,
+    H: BuildSeededHasher + Default,
+{
+    fn extend<I>(&mut self, kv: I)
+    where
+        I: IntoIterator<Item = (KOwned, V)>,
+    {
+        self.map.extend(kv);
+        self.reindex();
+    }
+}
+
+impl<KOwned, V, KRef, H> FromIterator<(KOwned, V)> for PhInverseMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    V: Hash + Eq,
+    H: BuildSeededHasher + Default,
+{
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (KOwned, V)>,
+    {
+        Self::new(kvs.into_iter().collect())
+    }
+}
@@ -0,0 +1,269 @@
+use std::{hash::Hash, iter::FusedIterator, marker::PhantomData, ptr::NonNull};
+
+use ph::BuildSeededHasher;
+
+use crate::bytes_map::Shard;
+use crate::inline_bytes::InlineBytes;
+use crate::{KeyHasher, slot_function::SlotFunction};
+
+/// Iterator over the entries of a [`PhMap`](crate::PhMap) along with their slot index,
+/// created by [`PhMap::iter_indexed`](crate::PhMap::iter_indexed).
+pub struct IterIndexed<'a, KOwned, V, KRef: ?Sized = KOwned, H = KeyHasher> {
+    pub(crate) keys: std::slice::Iter<'a, KOwned>,
+    pub(crate) values: &'a [Option<V>],
+    pub(crate) to_index: &'a SlotFunction<H>,
+    pub(crate) _phantom: PhantomData<fn(&KRef)>,
+}
+
+impl<'a, KOwned, V, KRef, H> Iterator for IterIndexed<'a, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+    type Item = (usize, &'a KOwned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.keys.next()?;
+        let idx = unsafe { self.to_index.get(key.as_ref()).unwrap_unchecked() };
+        let value = unsafe { self.values.get_unchecked(idx).as_ref().unwrap_unchecked() };
+
+        Some((idx, key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<KOwned, V, KRef, H> ExactSizeIterator for IterIndexed<'_, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+}
+
+impl<KOwned, V, KRef, H> FusedIterator for IterIndexed<'_, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+}
+
+/// Iterator over the entries of a [`PhMap`](crate::PhMap) in insertion order, created by
+/// [`PhMap::iter`](crate::PhMap::iter).
+pub struct Iter<'a, KOwned, V, KRef: ?Sized = KOwned, H = KeyHasher> {
+    pub(crate) inner: IterIndexed<'a, KOwned, V, KRef, H>,
+}
+
+impl<'a, KOwned, V, KRef, H> Iterator for Iter<'a, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+    type Item = (&'a KOwned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, key, value) = self.inner.next()?;
+
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<KOwned, V, KRef, H> ExactSizeIterator for Iter<'_, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+}
+
+impl<KOwned, V, KRef, H> FusedIterator for Iter<'_, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+}
+
+/// Mutable iterator over the entries of a [`PhMap`](crate::PhMap) in insertion order, created
+/// by [`PhMap::iter_mut`](crate::PhMap::iter_mut).
+pub struct IterMut<'a, KOwned, V, KRef: ?Sized = KOwned, H = KeyHasher> {
+    pub(crate) keys: std::slice::Iter<'a, KOwned>,
+    /// Start of the map's value slots. Every key maps to a different slot, so each slot is
+    /// borrowed at most once by the references this returns.
+    pub(crate) values: NonNull<Option<V>>,
+    pub(crate) to_index: &'a SlotFunction<H>,
+    pub(crate) _values: PhantomData<&'a mut [Option<V>]>,
+    pub(crate) _phantom: PhantomData<fn(&KRef)>,
+}
+
+impl<'a, KOwned, V, KRef, H> Iterator for IterMut<'a, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+    type Item = (&'a KOwned, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.keys.next()?;
+        let value = unsafe {
+            let idx = self.to_index.get(key.as_ref()).unwrap_unchecked();
+            (*self.values.add(idx).as_ptr()).as_mut().unwrap_unchecked()
+        };
+
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<KOwned, V, KRef, H> ExactSizeIterator for IterMut<'_, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+}
+
+impl<KOwned, V, KRef, H> FusedIterator for IterMut<'_, KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+}
+
+/// Owning iterator over the entries of a [`PhMap`](crate::PhMap) in insertion order. Entries
+/// which aren't consumed are dropped along with the iterator.
+pub struct IntoIter<KOwned, V, KRef: ?Sized = KOwned, H = KeyHasher> {
+    pub(crate) keys: std::vec::IntoIter<KOwned>,
+    pub(crate) values: Vec<Option<V>>,
+    pub(crate) to_index: SlotFunction<H>,
+    pub(crate) _phantom: PhantomData<fn(&KRef)>,
+}
+
+impl<KOwned, V, KRef, H> Iterator for IntoIter<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+    type Item = (KOwned, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.keys.next()?;
+        let value = unsafe {
+            let idx = self.to_index.get(key.as_ref()).unwrap_unchecked();
+            self.values.get_unchecked_mut(idx).take().unwrap_unchecked()
+        };
+
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<KOwned, V, KRef, H> ExactSizeIterator for IntoIter<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+}
+
+impl<KOwned, V, KRef, H> FusedIterator for IntoIter<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+}
+
+/// Iterator over the values of a [`PhStrMap`](crate::PhStrMap) or
+/// [`PhBytesMap`](crate::PhBytesMap).
+pub struct StrValues<'a, V> {
+    pub(crate) shards: std::slice::Iter<'a, Shard<V>>,
+    pub(crate) values: std::slice::Iter<'a, Option<V>>,
+}
+
+impl<'a, V> Iterator for StrValues<'a, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.values.by_ref().flatten().next() {
+                return Some(value);
+            }
+
+            self.values = self.shards.next()?.inner_map.values.iter();
+        }
+    }
+}
+
+impl<V> FusedIterator for StrValues<'_, V> {}
+
+/// Iterator over the entries of a [`PhBytesMap`](crate::PhBytesMap), created by
+/// [`PhBytesMap::iter`](crate::PhBytesMap::iter).
+pub struct BytesIter<'a, V> {
+    pub(crate) shards: std::slice::Iter<'a, Shard<V>>,
+    pub(crate) shard: Option<&'a Shard<V>>,
+    pub(crate) entries: Option<Iter<'a, InlineBytes, V, [u8]>>,
+    /// Position of the next entry of `shard`, for finding its full key.
+    pub(crate) position: usize,
+}
+
+impl<'a, V> Iterator for BytesIter<'a, V> {
+    type Item = (&'a [u8], &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(shard) = self.shard
+                && let Some((_, value)) = self.entries.as_mut().and_then(Iterator::next)
+            {
+                let key = shard.key(self.position)?;
+                self.position += 1;
+
+                return Some((key, value));
+            }
+
+            let shard = self.shards.next()?;
+            self.shard = Some(shard);
+            self.entries = Some(shard.inner_map.iter());
+            self.position = 0;
+        }
+    }
+}
+
+impl<V> FusedIterator for BytesIter<'_, V> {}
+
+/// Iterator over the entries of a [`PhStrMap`](crate::PhStrMap), created by
+/// [`PhStrMap::iter`](crate::PhStrMap::iter).
+pub struct StrIter<'a, V> {
+    pub(crate) inner: BytesIter<'a, V>,
+}
+
+impl<'a, V> Iterator for StrIter<'a, V> {
+    type Item = (&'a str, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.inner.next()?;
+
+        // Keys are only inserted into a `PhStrMap` as `str`s.
+        Some((unsafe { std::str::from_utf8_unchecked(key) }, value))
+    }
+}
+
+impl<V> FusedIterator for StrIter<'_, V> {}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use itertools::Itertools;
+
+use crate::smallest_uncommon_range;
+
+/// Most positions selected by [`KeyCompression::Positions`] for keys of a single length.
+pub(crate) const MAX_POSITIONS: usize = 16;
+
+/// How a [`PhStrMap`](crate::PhStrMap) picks the bytes of each key which are hashed and
+/// compared. Every strategy is applied separately to each key length, and always selects
+/// enough bytes to tell every key of that length apart. Set with
+/// [`MapOptions::compression`](crate::MapOptions::compression).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum KeyCompression {
+    /// Hash the whole key.
+    Full,
+    /// Drop the prefix shared by every key, for keys like `user-1234`.
+    StripPrefix,
+    /// Drop the suffix shared by every key, for keys like `1234.png`.
+    StripSuffix,
+    /// Only hash the smallest contiguous range which still tells the keys apart.
+    #[default]
+    Range,
+    /// Hash a small set of byte positions, which don't have to be next to each other, for keys
+    /// like `user-1234-region-5` where the distinguishing bytes are spread out. The positions
+    /// are picked greedily, and if more than 16 would be needed this falls back to
+    /// [`KeyCompression::Range`].
+    Positions,
+}
+
+/// The bytes of each key in a shard which are hashed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Selection {
+    Range(Range<usize>),
+    /// Sorted, and at most [`MAX_POSITIONS`] long.
+    Positions(Box<[usize]>),
+}
+
+impl Selection {
+    /// Number of bytes selected from each key.
+    pub(crate) fn selected_len(&self) -> usize {
+        match self {
+            Self::Range(range) => range.len(),
+            Self::Positions(positions) => positions.len(),
+        }
+    }
+
+    /// The selected bytes of `key`, using `buf` to gather positions. Returns `None` if `key`
+    /// is too short.
+    #[inline]
+    pub(crate) fn select<'k>(
+        &self,
+        key: &'k [u8],
+        buf: &'k mut [u8; MAX_POSITIONS],
+    ) -> Option<&'k [u8]> {
+        match self {
+            Self::Range(range) => key.get(range.clone()),
+            Self::Positions(positions) => {
+                for (byte, position) in buf.iter_mut().zip(positions) {
+                    *byte = *key.get(*position)?;
+                }
+
+                buf.get(..positions.len())
+            }
+        }
+    }
+
+    /// # Safety
+    /// `key` must be long enough to contain the selection.
+    #[inline]
+    pub(crate) unsafe fn select_unchecked<'k>(
+        &self,
+        key: &'k [u8],
+        buf: &'k mut [u8; MAX_POSITIONS],
+    ) -> &'k [u8] {
+        match self {
+            Self::Range(range) => unsafe { key.get_unchecked(range.clone()) },
+            Self::Positions(positions) => {
+                for (byte, position) in buf.iter_mut().zip(positions) {
+                    *byte = unsafe { *key.get_unchecked(*position) };
+                }
+
+                unsafe { buf.get_unchecked(..positions.len()) }
+            }
+        }
+    }
+}
+
+impl KeyCompression {
+    /// The bytes of each key to hash. Every key must have the same length.
+    pub(crate) fn select<'a, I>(self, keys: I) -> Selection
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+        I::IntoIter: Clone,
+    {
+        let keys = keys.into_iter();
+        let len = keys.clone().map(<[u8]>::len).max().unwrap_or(0);
+        let common = |i: usize| keys.clone().map(|k| k.get(i)).all_equal();
+
+        // A single key has nothing to tell apart, so every strategy would select no bytes and
+        // any key of the same length would be found. Hash the whole key instead.
+        if keys.clone().nth(1).is_none() {
+            return Selection::Range(0..len);
+        }
+
+        let range = match self {
+            Self::Full => 0..len,
+            Self::StripPrefix => (0..len).find(|&i| !common(i)).unwrap_or(len)..len,
+            Self::StripSuffix => 0..(0..len).rev().find(|&i| !common(i)).map_or(0, |i| i + 1),
+            Self::Range => smallest_uncommon_range(keys),
+            Self::Positions => {
+                return match discriminating_positions(keys.clone(), len) {
+                    Some(positions) => Selection::Positions(positions.into()),
+                    None => Selection::Range(smallest_uncommon_range(keys)),
+                };
+            }
+        };
+
+        Selection::Range(range)
+    }
+}
+
+/// Greedily picks the position which splits the keys into the most groups, until every key is
+/// in its own group. Returns `None` if that takes more than [`MAX_POSITIONS`] positions, or if
+/// the keys aren't unique.
+fn discriminating_positions<'a, I>(keys: I, len: usize) -> Option<Vec<usize>>
+where
+    I: Iterator<Item = &'a [u8]> + Clone,
+{
+    let num_keys = keys.clone().count();
+    let mut groups = vec![0; num_keys];
+    let mut num_groups = 1;
+    let mut positions = vec![];
+
+    while num_groups < num_keys {
+        if positions.len() == MAX_POSITIONS {
+            return None;
+        }
+
+        let (position, split, num_split) = (0..len)
+            .filter(|position| !positions.contains(position))
+            .map(|position| {
+                let mut ids = HashMap::new();
+                let split = keys
+                    .clone()
+                    .zip(&groups)
+                    .map(|(key, group)| {
+                        let next = ids.len();
+                        *ids.entry((*group, key.get(position))).or_insert(next)
+                    })
+                    .collect::<Vec<_>>();
+
+                (position, split, ids.len())
+            })
+            .max_by_key(|(_, _, num_split)| *num_split)?;
+
+        if num_split == num_groups {
+            return None;
+        }
+
+        positions.push(position);
+        groups = split;
+        num_groups = num_split;
+    }
+
+    positions.sort_unstable();
+    Some(positions)
+}
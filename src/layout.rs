@@ -0,0 +1,68 @@
+use std::{
+    fmt,
+    hash::Hash,
+    io::{self, Write},
+};
+
+use ph::BuildSeededHasher;
+
+use crate::PhMap;
+
+impl<KOwned, V, KRef, H> PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    /// Writes the index, stored hash and occupancy of every slot, followed by summary
+    /// statistics. Intended for debugging.
+    pub fn dump_layout<W>(&self, w: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.write_layout(w, |_| None::<&str>)
+    }
+
+    /// Like [`PhMap::dump_layout`], but also writes the key stored in each occupied slot.
+    pub fn dump_layout_with_keys<W>(&self, w: W) -> io::Result<()>
+    where
+        W: Write,
+        KOwned: fmt::Debug,
+    {
+        self.write_layout(w, Some)
+    }
+
+    fn write_layout<'a, W, F, D>(&'a self, mut w: W, mut describe_key: F) -> io::Result<()>
+    where
+        W: Write,
+        F: FnMut(&'a KOwned) -> Option<D>,
+        D: fmt::Debug,
+    {
+        writeln!(w, "{:>10} {:>18} {:>8}  key", "slot", "hash", "occupied")?;
+
+        for (idx, hash) in self.top_level_hashes.iter().enumerate() {
+            let occupied = self.is_slot_occupied(idx);
+            let key = self.get_by_index(idx).map(|(key, _)| key);
+
+            write!(w, "{idx:>10} {hash:#018x} {occupied:>8}")?;
+            match key.and_then(&mut describe_key) {
+                Some(key) => writeln!(w, "  {key:?}")?,
+                None => writeln!(w)?,
+            }
+        }
+
+        let slots = self.values.len();
+        let occupied = self.len();
+        let load_factor = if slots == 0 {
+            0.
+        } else {
+            occupied as f64 / slots as f64
+        };
+
+        writeln!(
+            w,
+            "slots: {slots}, occupied: {occupied}, holes: {}, load factor: {load_factor:.3}",
+            slots - occupied,
+        )
+    }
+}
@@ -1,247 +1,872 @@
 #![cfg_attr(feature = "benches", feature(test))]
 
-use std::ops::Range;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::ops::{Index, IndexMut, Range};
+use std::ptr::NonNull;
 use std::{hash::Hash, marker::PhantomData};
 
 use itertools::Itertools;
+#[cfg(not(feature = "portable-hash"))]
+use ph::BuildDefaultSeededHasher;
+use ph::BuildSeededHasher;
 use ph::seeds::BitsFast;
-use ph::{BuildDefaultSeededHasher, BuildSeededHasher};
 
-type Function = ph::phast::Perfect<BitsFast, ph::phast::SeedOnly, BuildDefaultSeededHasher>;
-pub struct PhMap<KOwned, V, KRef = KOwned>
+use fingerprints::Fingerprints;
+use inline_bytes::InlineBytes;
+use occupancy::Occupancy;
+use slot_function::SlotFunction;
+
+// The derives refer to `::ph_map`, which only resolves inside this crate with this alias.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as ph_map;
+
+mod batch;
+mod bi_map;
+mod builder;
+mod bytes_key;
+mod bytes_map;
+mod compressed;
+mod debug;
+mod dense;
+mod duplicates;
+mod entry;
+mod enum_map;
+mod error;
+mod fingerprints;
+mod frozen;
+mod index_map;
+mod inline_bytes;
+mod int_map;
+mod interned;
+mod interner;
+mod inverse;
+mod iter;
+mod key_compression;
+mod layout;
+mod lookup;
+mod memory;
+mod nested;
+#[cfg(feature = "unicode-normalization")]
+mod normalize;
+mod occupancy;
+mod options;
+mod ordered;
+mod pair;
+mod path_map;
+mod perfect_map;
+#[cfg(feature = "portable-hash")]
+mod portable;
+mod prefix_map;
+mod profile;
+mod rebuild_stats;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod set;
+mod sharded;
+mod slot_function;
+#[cfg(feature = "alloc-stats")]
+mod stats;
+mod str_set;
+mod tagged;
+mod verify;
+
+pub use bi_map::PhBiMap;
+pub use builder::PhMapBuilder;
+pub use bytes_key::PhBytesKey;
+pub use bytes_map::PhBytesMap;
+pub use compressed::{Codec, PhCompressedMap, RunLength, RunLengthError};
+pub use dense::PhDenseMap;
+pub use duplicates::Duplicates;
+pub use entry::{Entry, EntryBatch, OccupiedEntry, VacantEntry};
+pub use enum_map::{PhEnumMap, PhKey};
+pub use error::{BuildError, IntegrityError};
+pub use frozen::FrozenPhMap;
+pub use index_map::PhIndexMap;
+pub use int_map::{IntKey, PhIntMap};
+pub use interned::PhInternedMap;
+pub use interner::{KeyId, PhStrInterner};
+pub use inverse::{InverseIndex, PhInverseMap};
+pub use iter::{BytesIter, IntoIter, Iter, IterIndexed, IterMut, StrIter, StrValues};
+pub use key_compression::KeyCompression;
+pub use lookup::LookupResult;
+pub use memory::{CompressionStats, MemoryEstimate, MemoryUsage};
+pub use nested::Ph2Map;
+#[cfg(feature = "unicode-normalization")]
+pub use normalize::NormalizationForm;
+pub use options::MapOptions;
+pub use ordered::OrderedIndex;
+pub use pair::{Pair, PhPairMap};
+pub use path_map::PhPathMap;
+pub use perfect_map::PerfectMap;
+#[cfg(feature = "derive")]
+pub use ph_map_derive::{PhBytesKey, PhKey};
+#[cfg(feature = "portable-hash")]
+pub use portable::{BuildPortableHasher, PortableHasher};
+pub use prefix_map::PhPrefixMap;
+pub use profile::Profile;
+pub use rebuild_stats::RebuildStats;
+#[cfg(feature = "serde")]
+pub use serde_impl::DeserializeInto;
+pub use set::PhSet;
+pub use sharded::PhShardedMap;
+#[cfg(feature = "alloc-stats")]
+pub use stats::BuildStats;
+pub use str_set::PhStrSet;
+pub use tagged::PhTaggedMap;
+
+/// Builds a [`PhMap`] from a list of `key => value` pairs, constructing the perfect hash
+/// function once for all of the entries.
+///
+/// ```
+/// let map: ph_map::PhMap<&str, u32, str> = ph_map::phmap! { "a" => 1, "b" => 2 };
+/// assert_eq!(map.get("b"), Some(&2));
+/// ```
+#[macro_export]
+macro_rules! phmap {
+    () => {
+        $crate::PhMap::default()
+    };
+    ($($key:expr => $value:expr),+ $(,)?) => {
+        $crate::PhMap::from([$(($key, $value)),+])
+    };
+}
+
+#[cfg(all(feature = "portable-hash", feature = "gxhash"))]
+compile_error!(
+    "`gxhash` gives different results on different platforms, so it can't be used with `portable-hash`"
+);
+
+#[cfg(not(feature = "portable-hash"))]
+type KeyHasher = BuildDefaultSeededHasher;
+#[cfg(feature = "portable-hash")]
+type KeyHasher = BuildPortableHasher;
+
+type Function<H = KeyHasher> = ph::phast::Perfect<BitsFast, ph::phast::SeedOnly, H>;
+
+/// A map built on a perfect hash function over its keys. `H` is the hasher the function uses,
+/// which defaults to the one chosen by the `gxhash` and `portable-hash` features. Other
+/// hashers can be picked with [`PhMapBuilder::hasher`].
+pub struct PhMap<KOwned, V, KRef = KOwned, H = KeyHasher>
 where
     KRef: ?Sized + Hash,
     KOwned: AsRef<KRef>,
 {
     keys: Vec<KOwned>,
-    top_level_hashes: Vec<u64>,
+    top_level_hashes: Fingerprints,
     values: Vec<Option<V>>,
-    to_index: Function,
+    occupied: Occupancy,
+    /// Index into `keys` of the key stored in each occupied slot.
+    key_indices: Vec<u32>,
+    to_index: SlotFunction<H>,
+    profile: Profile,
+    /// Whether rebuilds check that every key has its own slot, even without debug assertions.
+    verify_slots: bool,
+    /// Number of keys removed since the last rebuild, whose slots are empty until the next one.
+    removed: usize,
+    rebuild_stats: RebuildStats,
+    #[cfg(feature = "alloc-stats")]
+    build_stats: BuildStats,
     _phantom: PhantomData<fn(&KRef)>,
 }
 
+/// A [`PhMap`] keyed by strings which can be borrowed or owned, so that a map built from a mix
+/// of both only allocates for the owned keys. The lifetime is that of the borrowed keys.
+pub type PhCowStrMap<'a, V> = PhMap<Cow<'a, str>, V, str>;
+
+/// Like [`PhCowStrMap`], but keyed by byte strings.
+pub type PhCowBytesMap<'a, V> = PhMap<Cow<'a, [u8]>, V, [u8]>;
+
+/// A map keyed by strings, which only hashes the smallest range of bytes that distinguishes
+/// the keys. This is a [`PhBytesMap`] whose keys are known to be UTF-8.
+#[derive(Clone)]
 pub struct PhStrMap<V> {
-    range: Range<usize>,
-    inner_map: PhMap<Vec<u8>, V, [u8]>,
+    inner: PhBytesMap<V>,
 }
 
 impl<V> Default for PhStrMap<V> {
     fn default() -> Self {
-        Self {
-            range: 0..0,
-            inner_map: Default::default(),
-        }
+        Self::with_options(MapOptions::default())
     }
 }
 
 impl<V> PhStrMap<V> {
-    pub fn insert(&mut self, key: String, value: V) {
-        self.extend(std::iter::once((key, value)))
+    pub fn with_options(options: MapOptions) -> Self {
+        Self {
+            inner: PhBytesMap::with_options(options),
+        }
+    }
+
+    pub fn options(&self) -> MapOptions {
+        self.inner.options()
+    }
+
+    #[cfg(not(feature = "unicode-normalization"))]
+    #[inline]
+    fn normalize<'k>(&self, key: &'k str) -> &'k str {
+        key
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[inline]
+    fn normalize<'k>(&self, key: &'k str) -> Cow<'k, str> {
+        match self.inner.options.normalization {
+            Some(form) => form.normalize(key),
+            None => Cow::Borrowed(key),
+        }
+    }
+
+    /// Converts keys being inserted to bytes, normalizing them first if the map normalizes
+    /// keys. This doesn't borrow the map, so it can be used while extending it.
+    fn key_to_bytes(&self) -> impl Fn(Cow<'static, str>) -> Cow<'static, [u8]> + use<V> {
+        #[cfg(feature = "unicode-normalization")]
+        let normalization = self.inner.options.normalization;
+
+        move |key| {
+            #[cfg(feature = "unicode-normalization")]
+            let key = match normalization {
+                Some(form) => form.normalize_cow(key),
+                None => key,
+            };
+
+            match key {
+                Cow::Borrowed(key) => Cow::Borrowed(key.as_bytes()),
+                Cow::Owned(key) => Cow::Owned(key.into_bytes()),
+            }
+        }
+    }
+
+    /// Builds a map from a newline-delimited list of keys, such as a dictionary file, with the
+    /// value for each key given by `value`. Lines are read into a reused buffer rather than
+    /// collected first. Line endings are stripped and empty lines are skipped.
+    pub fn from_lines<R, F>(mut reader: R, mut value: F) -> io::Result<Self>
+    where
+        R: BufRead,
+        F: FnMut(&str) -> V,
+    {
+        let mut line = String::new();
+        let mut error = None;
+        let entries = std::iter::from_fn(|| {
+            loop {
+                line.clear();
+                match reader.read_line(&mut line) {
+                    Ok(0) => return None,
+                    Ok(_) => {}
+                    Err(e) => {
+                        error = Some(e);
+                        return None;
+                    }
+                }
+
+                let key = line.strip_suffix('\n').unwrap_or(&line);
+                let key = key.strip_suffix('\r').unwrap_or(key);
+                if !key.is_empty() {
+                    return Some((InlineBytes::from(key.as_bytes()), value(key)));
+                }
+            }
+        });
+
+        let mut map = Self::default();
+        map.inner.extend_bytes(entries);
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(map),
+        }
+    }
+
+    /// See [`PhBytesMap::insert`].
+    pub fn insert(&mut self, key: String, value: V) -> Option<V> {
+        let to_bytes = self.key_to_bytes();
+
+        self.inner
+            .insert(to_bytes(Cow::Owned(key)).into_owned(), value)
     }
 
-    pub fn extend<KV>(&mut self, kv: KV)
+    /// See [`PhBytesMap::try_extend`].
+    pub fn try_extend<KV>(&mut self, kv: KV) -> Result<(), BuildError>
     where
         KV: IntoIterator<Item = (String, V)>,
     {
-        let mut kvs: Vec<(Vec<u8>, V)> = kv.into_iter().map(|(k, v)| (k.into_bytes(), v)).collect();
-        let range = smallest_uncommon_range(kvs.iter().map(|(k, _)| &**k));
+        let to_bytes = self.key_to_bytes();
 
-        kvs.iter_mut().for_each(|(k, _)| {
-            let substring = k[range.clone()].to_owned();
-            *k = substring;
-        });
+        self.inner.try_extend(
+            kv.into_iter()
+                .map(|(k, v)| (to_bytes(Cow::Owned(k)).into_owned(), v)),
+        )
+    }
+
+    /// Iterates over the entries, grouped by key length in the order each length was first
+    /// inserted, and in insertion order within each length.
+    pub fn iter(&self) -> StrIter<'_, V> {
+        StrIter {
+            inner: self.inner.iter(),
+        }
+    }
 
-        assert!(range == self.range || self.range.is_empty());
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|(key, _)| key)
+    }
 
-        self.range = range;
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
 
-        self.inner_map.extend(kvs);
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
     }
 
+    /// Keys are looked up by length first, so a key which is too short to contain the range
+    /// of any shard returns `None` rather than being sliced.
+    #[inline]
+    #[cfg_attr(
+        all(
+            feature = "no-panic",
+            not(debug_assertions),
+            not(feature = "unicode-normalization")
+        ),
+        no_panic::no_panic
+    )]
     pub fn get<K>(&self, key: &K) -> Option<&V>
     where
         K: ?Sized + AsRef<str>,
     {
-        self.inner_map
-            .get(key.as_ref().as_bytes().get(self.range.clone())?)
+        self.inner.get(self.normalize(key.as_ref()).as_bytes())
+    }
+
+    /// Whether `key` is in the map. This does the same checks as [`PhStrMap::get`], but
+    /// doesn't read the value.
+    #[inline]
+    #[cfg_attr(
+        all(
+            feature = "no-panic",
+            not(debug_assertions),
+            not(feature = "unicode-normalization")
+        ),
+        no_panic::no_panic
+    )]
+    pub fn contains_key<K>(&self, key: &K) -> bool
+    where
+        K: ?Sized + AsRef<str>,
+    {
+        self.inner
+            .contains_key(self.normalize(key.as_ref()).as_bytes())
     }
 
     /// # Safety
     /// `key` must be in the map.
+    #[inline]
+    #[cfg_attr(
+        all(
+            feature = "no-panic",
+            not(debug_assertions),
+            not(feature = "unicode-normalization")
+        ),
+        no_panic::no_panic
+    )]
     pub unsafe fn get_unchecked<K>(&self, key: &K) -> &V
     where
         K: ?Sized + AsRef<str>,
     {
         unsafe {
-            self.inner_map
-                .get_unchecked(key.as_ref().as_bytes().get_unchecked(self.range.clone()))
+            self.inner
+                .get_unchecked(self.normalize(key.as_ref()).as_bytes())
         }
     }
 
+    /// See [`PhBytesMap::compression_stats`].
+    pub fn compression_stats(&self) -> CompressionStats {
+        self.inner.compression_stats()
+    }
+
+    /// See [`PhBytesMap::get_batch`].
+    pub fn get_batch<K>(&self, keys: &[K]) -> Vec<Option<&V>>
+    where
+        K: AsRef<str>,
+    {
+        #[cfg(feature = "unicode-normalization")]
+        if self.inner.options.normalization.is_some() {
+            let keys = keys
+                .iter()
+                .map(|key| self.normalize(key.as_ref()))
+                .collect::<Vec<_>>();
+
+            return self
+                .inner
+                .get_batch_from(keys.iter().map(|key| key.as_bytes()));
+        }
+
+        self.inner
+            .get_batch_from(keys.iter().map(|key| key.as_ref().as_bytes()))
+    }
+
+    #[inline]
+    #[cfg_attr(
+        all(
+            feature = "no-panic",
+            not(debug_assertions),
+            not(feature = "unicode-normalization")
+        ),
+        no_panic::no_panic
+    )]
     pub fn get_mut<K>(&mut self, key: &K) -> Option<&mut V>
     where
         K: ?Sized + AsRef<str>,
     {
-        self.inner_map
-            .get_mut(key.as_ref().as_bytes().get(self.range.clone())?)
+        let key = self.normalize(key.as_ref());
+
+        self.inner.get_mut(key.as_bytes())
     }
 
     /// # Safety
     /// `key` must be in the map.
+    #[inline]
+    #[cfg_attr(
+        all(
+            feature = "no-panic",
+            not(debug_assertions),
+            not(feature = "unicode-normalization")
+        ),
+        no_panic::no_panic
+    )]
     pub unsafe fn get_unchecked_mut<K>(&mut self, key: &K) -> &mut V
     where
         K: ?Sized + AsRef<str>,
     {
-        unsafe {
-            self.inner_map
-                .get_unchecked_mut(key.as_ref().as_bytes().get_unchecked(self.range.clone()))
-        }
+        let key = self.normalize(key.as_ref());
+
+        unsafe { self.inner.get_unchecked_mut(key.as_bytes()) }
+    }
+
+    /// See [`PhBytesMap::remove`].
+    pub fn remove<K>(&mut self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<str>,
+    {
+        let key = self.normalize(key.as_ref());
+
+        self.inner.remove(key.as_bytes())
+    }
+
+    /// See [`PhBytesMap::maintain`].
+    pub fn maintain(&mut self) -> bool {
+        self.inner.maintain()
+    }
+
+    /// See [`PhBytesMap::clear`].
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    /// See [`PhBytesMap::retain`].
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&str, &mut V) -> bool,
+    {
+        // Keys are only inserted as `str`s.
+        self.inner
+            .retain(|key, value| f(unsafe { std::str::from_utf8_unchecked(key) }, value))
+    }
+
+    /// Leaks the map, returning a shared reference which lives for the rest of the program.
+    pub fn leak(self) -> &'static Self
+    where
+        Self: 'static,
+    {
+        Box::leak(Box::new(self))
+    }
+
+    pub fn get_copied<K>(&self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<str>,
+        V: Copy,
+    {
+        self.get(key).copied()
+    }
+
+    pub fn get_cloned<K>(&self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<str>,
+        V: Clone,
+    {
+        self.get(key).cloned()
+    }
+}
+
+impl<V> Extend<(String, V)> for PhStrMap<V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (String, V)>,
+    {
+        self.extend(kv.into_iter().map(|(k, v)| (Cow::Owned(k), v)))
+    }
+}
+
+/// String literals are copied straight into their shard's key buffer, without an allocation
+/// for each key.
+impl<V> Extend<(&'static str, V)> for PhStrMap<V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (&'static str, V)>,
+    {
+        self.extend(kv.into_iter().map(|(k, v)| (Cow::Borrowed(k), v)))
+    }
+}
+
+impl<V> Extend<(Cow<'static, str>, V)> for PhStrMap<V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (Cow<'static, str>, V)>,
+    {
+        let to_bytes = self.key_to_bytes();
+
+        self.inner
+            .extend(kv.into_iter().map(|(k, v)| (to_bytes(k), v)))
     }
 }
 
-impl<KOwned, V, KRef> Default for PhMap<KOwned, V, KRef>
+impl<KOwned, V, KRef, H> Default for PhMap<KOwned, V, KRef, H>
 where
     KRef: ?Sized + Hash,
     KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
 {
     fn default() -> Self {
-        let keys: &[&KRef] = &[];
-        let to_index = Function::with_slice_p_hash_sc(
-            keys,
-            &ph::phast::Params::new(BitsFast(0), ph::phast::bits_per_seed_to_100_bucket_size(0)),
-            BuildDefaultSeededHasher::default(),
-            ph::phast::SeedOnly,
-        );
         Self {
             keys: vec![],
             values: vec![],
-            top_level_hashes: vec![],
-            to_index,
+            top_level_hashes: Fingerprints::default(),
+            occupied: Occupancy::default(),
+            key_indices: vec![],
+            to_index: SlotFunction::sparse(empty_function::<KRef, H>()),
             // member_set: Set::default(),
+            profile: Profile::default(),
+            verify_slots: false,
+            removed: 0,
+            rebuild_stats: RebuildStats::default(),
+            #[cfg(feature = "alloc-stats")]
+            build_stats: BuildStats::default(),
             _phantom: PhantomData,
         }
     }
 }
 
-impl<KOwned, V, KRef> PhMap<KOwned, V, KRef>
+impl<KOwned, V, KRef, H> PhMap<KOwned, V, KRef, H>
 where
     KRef: ?Sized + Hash,
     KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
 {
-    pub fn insert(&mut self, key: KOwned, value: V) {
-        self.extend(std::iter::once((key, value)))
+    /// Creates an empty map whose hash function is built with the parameters of `profile`.
+    pub fn with_profile(profile: Profile) -> Self {
+        Self {
+            top_level_hashes: Fingerprints::new(profile.fingerprint_bits()),
+            profile,
+            ..Default::default()
+        }
+    }
+
+    /// Builds a map from `values`, deriving each value's key with `key_fn`.
+    pub fn from_values_with_key<I, F>(values: I, key_fn: F) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        F: Fn(&V) -> KOwned,
+    {
+        let mut map = Self::default();
+        map.extend(values.into_iter().map(|value| (key_fn(&value), value)));
+        map
+    }
+
+    /// Inserts `value` for `key`, returning the previous value if `key` was already in the map.
+    /// The map is only rebuilt if `key` is new.
+    pub fn insert(&mut self, key: KOwned, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Moves all entries from `other` into this map with a single rebuild, leaving `other`
+    /// empty.
+    pub fn append(&mut self, other: &mut Self) {
+        let mut other = std::mem::take(other);
+
+        self.extend(other.keys.drain(..).map(|key| {
+            let value = unsafe {
+                other
+                    .to_index
+                    .take_unchecked(&mut other.values, key.as_ref())
+            };
+
+            (key, value)
+        }));
+    }
+
+    /// Gets the entry for `key`. Inserting into a vacant entry rebuilds the map.
+    pub fn entry(&mut self, key: KOwned) -> Entry<'_, KOwned, V, KRef, H> {
+        match self.find_index(key.as_ref()) {
+            Some(idx) => Entry::Occupied(OccupiedEntry {
+                key,
+                idx,
+                map: self,
+            }),
+            None => Entry::Vacant(VacantEntry { key, map: self }),
+        }
+    }
+
+    /// Returns the value for `key`, inserting the result of `default` if it isn't in the map.
+    /// The map is only rebuilt if `key` is new.
+    pub fn get_or_insert_with<F>(&mut self, key: KOwned, default: F) -> &mut V
+    where
+        F: FnOnce() -> V,
+    {
+        self.entry(key).or_insert_with(default)
     }
 
-    pub fn extend<KV>(&mut self, kv: KV)
+    /// Starts staging insertions, which are applied with a single rebuild by
+    /// [`EntryBatch::commit`].
+    pub fn entry_batch(&mut self) -> EntryBatch<'_, KOwned, V, KRef, H> {
+        EntryBatch {
+            map: self,
+            staged: vec![],
+            staged_by_hash: HashMap::new(),
+        }
+    }
+
+    /// Like [`Extend::extend`], but returns an error instead of panicking if the map can't
+    /// be built. On error, the map is unchanged.
+    pub fn try_extend<KV>(&mut self, kv: KV) -> Result<(), BuildError>
     where
         KV: IntoIterator<Item = (KOwned, V)>,
     {
-        unsafe { self.values.set_len(0) };
+        self.rebuild(kv.into_iter().map(|(key, value)| {
+            let hash = Self::hash_key(key.as_ref());
 
-        let hasher = &self.to_index.hasher();
+            (key, hash, value)
+        }))
+    }
 
-        let (keys, values_and_key_hashes): (Vec<_>, Vec<_>) = self
-            .keys
-            .drain(..)
-            .map(|key| {
-                let value =
-                    unsafe { take_unchecked(&mut self.values, &self.to_index, key.as_ref()) };
+    /// The hash used to place `key`. This only depends on the key, so it can be computed
+    /// ahead of time (e.g. on another thread) and passed to [`PhMap::extend_prehashed`].
+    pub fn hash_key(key: &KRef) -> u64 {
+        H::default().hash_one(key, 0)
+    }
 
-                (key, value)
-            })
-            .chain(kv)
-            .map(|(key, value)| {
-                let hash = hasher.hash_one(key.as_ref(), 0);
+    pub fn hash_keys<I>(keys: I) -> Vec<u64>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<KRef>,
+    {
+        keys.into_iter()
+            .map(|key| Self::hash_key(key.as_ref()))
+            .collect()
+    }
 
-                (key, (value, hash))
-            })
-            .unzip();
+    /// Like [`Extend::extend`], but using hashes computed by [`PhMap::hash_key`] instead of
+    /// hashing the new keys again.
+    ///
+    /// # Safety
+    /// Each hash must be the value returned by [`PhMap::hash_key`] for its key.
+    pub unsafe fn extend_prehashed<I>(&mut self, entries: I)
+    where
+        I: IntoIterator<Item = (KOwned, u64, V)>,
+    {
+        if let Err(e) = self.rebuild(entries) {
+            build_failed(e);
+        }
+    }
 
-        let bits = keys.len().next_power_of_two().ilog(2) + 1;
-        let bits_u8 = bits.try_into().unwrap();
-        {
-            let key_refs = keys.iter().map(|k| k.as_ref()).collect::<Vec<_>>();
-            self.to_index = Function::with_vec_p_hash_sc(
-                key_refs,
-                &ph::phast::Params::new(
-                    BitsFast(bits_u8),
-                    ph::phast::bits_per_seed_to_100_bucket_size(bits_u8),
-                ),
-                BuildDefaultSeededHasher::default(),
-                ph::phast::SeedOnly,
-            );
-        }
-
-        // We need to set a minimum value above 4, as for some reason Rust doesn't reserve
-        // correctly for small vectors.
-        let mut max_idx = keys.len();
-
-        self.top_level_hashes = vec![0; keys.len()];
-
-        let mut insert_one = |key: &KOwned, value: V, hash: u64| -> usize {
-            let idx = self
-                .to_index
-                .get_with_top_level_hash(key.as_ref(), hash)
-                .unwrap();
-
-            max_idx = max_idx.max(idx);
-
-            if let Some(extra_capacity) = (max_idx + 1).checked_sub(self.values.len()) {
-                self.values
-                    .extend(std::iter::from_fn(|| Some(None)).take(extra_capacity));
-            }
+    /// Builds a new function over the existing keys and `entries`, and moves every value to
+    /// its new slot. Everything which can fail is done before the map is changed, so on error
+    /// the map still holds its previous entries.
+    #[cold]
+    #[inline(never)]
+    fn rebuild<I>(&mut self, entries: I) -> Result<(), BuildError>
+    where
+        I: IntoIterator<Item = (KOwned, u64, V)>,
+    {
+        let entries = entries.into_iter().collect::<Vec<_>>();
+
+        let num_keys = checked_num_keys(self.keys.len(), entries.len())?;
+
+        let hashes = self
+            .keys
+            .iter()
+            .map(|key| Self::hash_key(key.as_ref()))
+            .chain(entries.iter().map(|(_, hash, _)| *hash))
+            .collect::<Vec<_>>();
+        self.record_allocation::<u64>(0, hashes.capacity());
 
-            self.top_level_hashes
-                .resize(self.top_level_hashes.len().max(max_idx + 1), 0);
+        let SlotLayout {
+            to_index,
+            indices,
+            num_slots,
+        } = SlotLayout::new(
+            self.keys
+                .iter()
+                .map(|k| k.as_ref())
+                .chain(entries.iter().map(|(k, _, _)| k.as_ref())),
+            &hashes,
+            self.profile,
+            self.verify_slots,
+        )?;
+        self.record_allocation::<&KRef>(0, num_keys);
+        self.record_allocation::<usize>(0, indices.capacity());
+
+        let values_capacity = self.values.capacity();
+        let hashes_bytes = self.top_level_hashes.size_bytes();
+        let key_indices_capacity = self.key_indices.capacity();
+
+        // Reserve while the old slots are still in place, so that a failed allocation leaves
+        // the map as it was.
+        self.keys
+            .try_reserve_exact(entries.len())
+            .map_err(BuildError::capacity_overflow(num_keys))?;
+        self.values
+            .try_reserve_exact(num_slots.saturating_sub(self.values.len()))
+            .map_err(BuildError::capacity_overflow(num_slots))?;
+        self.top_level_hashes
+            .try_reserve_exact(num_slots.saturating_sub(self.top_level_hashes.len()))
+            .map_err(BuildError::capacity_overflow(num_slots))?;
+        self.key_indices
+            .try_reserve_exact(num_slots.saturating_sub(self.key_indices.len()))
+            .map_err(BuildError::capacity_overflow(num_slots))?;
+
+        // Nothing below can fail.
+        let bytes_rehashed = self.keys.iter().map(|key| size_of_val(key.as_ref())).sum();
+        self.rebuild_stats.record(self.keys.len(), bytes_rehashed);
+
+        let mut values = Vec::with_capacity(num_keys);
+        values.extend(
+            self.keys
+                .iter()
+                .map(|key| unsafe { self.to_index.take_unchecked(&mut self.values, key.as_ref()) }),
+        );
+        self.record_allocation::<V>(0, values.capacity());
+
+        for (key, _, value) in entries {
+            self.keys.push(key);
+            values.push(value);
+        }
 
-            debug_assert!(self.values.len() > max_idx);
-            debug_assert!(self.top_level_hashes.len() > max_idx);
+        self.to_index = to_index;
+        place_slots(
+            &mut self.top_level_hashes,
+            &mut self.key_indices,
+            &mut self.occupied,
+            &indices,
+            &hashes,
+            num_slots,
+        );
 
+        self.values.clear();
+        self.values.resize_with(num_slots, || None);
+        for (idx, value) in indices.into_iter().zip(values) {
             unsafe {
                 *self.values.get_unchecked_mut(idx) = Some(value);
-                *self.top_level_hashes.get_unchecked_mut(idx) = hash;
             }
+        }
 
-            idx
-        };
-        let keys_and_values_and_hashes = keys.iter().zip(values_and_key_hashes);
+        self.record_allocation::<Option<V>>(values_capacity, self.values.capacity());
+        self.record_allocation::<u8>(hashes_bytes, self.top_level_hashes.size_bytes());
+        self.record_allocation::<u32>(key_indices_capacity, self.key_indices.capacity());
+        self.removed = 0;
 
-        #[cfg(debug_assertions)]
-        {
-            let all_indices_unique = keys_and_values_and_hashes
-                .map(move |(k, (v, h))| insert_one(k, v, h))
-                .all_unique();
+        Ok(())
+    }
 
-            assert!(all_indices_unique);
+    /// Rebuilds the function over the remaining keys if any were removed since the last
+    /// rebuild, reclaiming their slots, and returns whether it did. [`PhMap::remove`] leaves
+    /// the slot empty so that it stays cheap, so this can be called while the map is idle to
+    /// compact it ahead of time.
+    pub fn maintain(&mut self) -> bool {
+        if self.removed == 0 {
+            return false;
         }
 
-        #[cfg(not(debug_assertions))]
-        keys_and_values_and_hashes.for_each(move |(k, (v, h))| {
-            insert_one(k, v, h);
-        });
+        if let Err(e) = self.rebuild([]) {
+            build_failed(e);
+        }
+        true
+    }
 
-        self.values.shrink_to_fit();
+    /// Counters for every rebuild this map has performed since it was created.
+    pub fn rebuild_stats(&self) -> RebuildStats {
+        self.rebuild_stats
+    }
+
+    #[cfg_attr(not(feature = "alloc-stats"), expect(unused_variables))]
+    fn record_allocation<T>(&mut self, old_capacity: usize, new_capacity: usize) {
+        #[cfg(feature = "alloc-stats")]
+        self.build_stats.record::<T>(old_capacity, new_capacity);
+    }
+
+    /// Allocations made while building this map's slot storage, across every rebuild.
+    #[cfg(feature = "alloc-stats")]
+    pub fn build_stats(&self) -> BuildStats {
+        self.build_stats
+    }
+
+    /// `key` must hash identically to the `KRef` it is looking up.
+    #[inline]
+    fn find_index<Q>(&self, key: &Q) -> Option<usize>
+    where
+        Q: ?Sized + Hash,
+    {
+        self.find_index_prehashed(key, self.to_index.hasher().hash_one(key, 0))
+    }
+
+    /// Like [`PhMap::find_index`], where `hash` is the value returned by [`PhMap::hash_key`]
+    /// for `key`.
+    #[inline]
+    fn find_index_prehashed<Q>(&self, key: &Q, hash: u64) -> Option<usize>
+    where
+        Q: ?Sized + Hash,
+    {
+        find_slot(
+            &self.to_index,
+            &self.top_level_hashes,
+            &self.occupied,
+            key,
+            hash,
+        )
+    }
+
+    /// Inserts a key which must not already be in the map, returning a reference to its value.
+    fn insert_new(&mut self, key: KOwned, value: V) -> &mut V {
+        self.extend(std::iter::once((key, value)));
 
-        self.keys = keys;
+        // `extend` appends new keys after the existing ones.
+        let key = unsafe { self.keys.last().unwrap_unchecked() };
+        let idx = unsafe { self.to_index.get(key.as_ref()).unwrap_unchecked() };
+        unsafe {
+            self.values
+                .get_unchecked_mut(idx)
+                .as_mut()
+                .unwrap_unchecked()
+        }
     }
 
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
     pub fn get<K>(&self, key: &K) -> Option<&V>
     where
         K: ?Sized + AsRef<KRef>,
     {
         // TODO: This assumes that the `Hash` implementation for `KRef` is well-behaved,
         //       but does not cause unsafety if this is not the case.
-        let hash = self.to_index.hasher().hash_one(key.as_ref(), 0);
-        let idx = self.to_index.get_with_top_level_hash(key.as_ref(), hash)?;
-        if *self.top_level_hashes.get(idx)? == hash {
-            Some(unsafe { self.values.get_unchecked(idx).as_ref().unwrap_unchecked() })
-        } else {
-            None
-        }
+        let idx = self.find_index(key.as_ref())?;
+        Some(unsafe { self.values.get_unchecked(idx).as_ref().unwrap_unchecked() })
     }
 
     /// # Safety
     /// `key` must be in the map.
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
     pub unsafe fn get_unchecked<K>(&self, key: &K) -> &V
     where
         K: ?Sized + AsRef<KRef>,
@@ -250,27 +875,27 @@ where
         unsafe { self.values.get_unchecked(idx).as_ref().unwrap_unchecked() }
     }
 
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
     pub fn get_mut<K>(&mut self, key: &K) -> Option<&mut V>
     where
         K: ?Sized + AsRef<KRef>,
     {
         // TODO: This assumes that the `Hash` implementation for `KRef` is well-behaved,
         //       but does not cause unsafety if this is not the case.
-        let hash = self.to_index.hasher().hash_one(key.as_ref(), 0);
-        let idx = self.to_index.get_with_top_level_hash(key.as_ref(), hash)?;
-        if *self.top_level_hashes.get(idx)? == hash {
-            Some(unsafe {
-                self.values
-                    .get_unchecked_mut(idx)
-                    .as_mut()
-                    .unwrap_unchecked()
-            })
-        } else {
-            None
-        }
+        let idx = self.find_index(key.as_ref())?;
+        Some(unsafe {
+            self.values
+                .get_unchecked_mut(idx)
+                .as_mut()
+                .unwrap_unchecked()
+        })
     }
+
     /// # Safety
     /// `key` must be in the map.
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
     pub unsafe fn get_unchecked_mut<K>(&mut self, key: &K) -> &mut V
     where
         K: ?Sized + AsRef<KRef>,
@@ -283,73 +908,2923 @@ where
                 .unwrap_unchecked()
         }
     }
-}
 
-/// # Safety
-/// `to_index` must have been created with `key` as one of its keys, and `vals` must have a length
-/// of at least the maxmimum value that `to_index` can return.
-pub unsafe fn take_unchecked<K, V>(vals: &mut [Option<V>], to_index: &Function, key: &K) -> V
-where
-    K: ?Sized + Hash,
-{
-    let idx = unsafe { to_index.get(key).unwrap_unchecked() };
-    unsafe { vals.get_unchecked_mut(idx).take().unwrap_unchecked() }
-}
+    /// Reserves capacity for at least `additional` more slots than the map currently has, so
+    /// that rebuilds which grow the map by up to that many slots don't reallocate the slot
+    /// storage.
+    pub fn reserve_slots(&mut self, additional: usize) {
+        self.values.reserve(additional);
+        self.top_level_hashes.reserve(additional);
+        self.key_indices.reserve(additional);
+    }
 
-/// `strs` must be sorted.
-fn smallest_uncommon_range<'a, I>(strs: I) -> Range<usize>
-where
-    I: IntoIterator<Item = &'a [u8]>,
-    I::IntoIter: ExactSizeIterator + Clone,
-{
-    let strs = strs.into_iter();
-    let mut start = 0;
+    /// Releases spare capacity. Rebuilds never shrink the map's storage, so this is useful
+    /// after removing entries or once a map has stopped growing.
+    pub fn shrink_to_fit(&mut self) {
+        self.keys.shrink_to_fit();
+        self.values.shrink_to_fit();
+        self.top_level_hashes.shrink_to_fit();
+        self.key_indices.shrink_to_fit();
+    }
 
-    loop {
-        if !strs.clone().map(|i| i[start]).all_equal() {
-            break;
-        }
+    /// Iterates over all values in slot order, which is the order they are stored in memory.
+    pub fn values_dense(&self) -> impl Iterator<Item = &V> {
+        self.values.iter().flatten()
+    }
 
-        start += 1;
+    /// Moves the values into a contiguous slice, in slot order, without rebuilding the hash
+    /// function.
+    pub fn compact_values(self) -> PhDenseMap<KOwned, V, KRef, H> {
+        let mut values = Vec::with_capacity(self.len());
+        let inner = self.map_values(|value| {
+            let idx = values
+                .len()
+                .try_into()
+                .expect("more than `u32::MAX` values");
+            values.push(value);
+            idx
+        });
+
+        PhDenseMap { inner, values }
     }
 
-    let mut out = start..start + 1;
-    loop {
-        if strs.clone().map(|s| &s[out.clone()]).all_unique() {
-            println!("Found {out:?}");
-            break;
+    /// Replaces every value with the result of `f`, in slot order, keeping the same keys and
+    /// hash function.
+    fn map_values<W, F>(self, mut f: F) -> PhMap<KOwned, W, KRef, H>
+    where
+        F: FnMut(V) -> W,
+    {
+        PhMap {
+            keys: self.keys,
+            top_level_hashes: self.top_level_hashes,
+            values: self
+                .values
+                .into_iter()
+                .map(|value| value.map(&mut f))
+                .collect(),
+            occupied: self.occupied,
+            key_indices: self.key_indices,
+            to_index: self.to_index,
+            profile: self.profile,
+            verify_slots: self.verify_slots,
+            removed: self.removed,
+            rebuild_stats: self.rebuild_stats,
+            #[cfg(feature = "alloc-stats")]
+            build_stats: self.build_stats,
+            _phantom: PhantomData,
         }
+    }
 
-        out.end += 1;
+    /// Removes every entry, keeping the allocations for the keys, values and hashes.
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.values.clear();
+        self.top_level_hashes.clear();
+        self.key_indices.clear();
+        self.occupied.reset(0);
+        self.to_index = SlotFunction::sparse(empty_function::<KRef, H>());
+        self.removed = 0;
     }
 
-    out
-}
+    /// Removes every entry, returning them as an iterator in insertion order. The map is empty
+    /// afterwards, even if the iterator isn't fully consumed.
+    pub fn drain(&mut self) -> IntoIter<KOwned, V, KRef, H> {
+        let drained = std::mem::replace(self, Self::with_profile(self.profile));
+        self.verify_slots = drained.verify_slots;
+        self.rebuild_stats = drained.rebuild_stats;
 
-#[cfg(test)]
-mod test {
-    use std::hash::{Hash as _, Hasher as _};
+        drained.into_iter()
+    }
 
-    use super::smallest_uncommon_range;
-    use crate::PhMap;
+    /// Keeps only the entries for which `f` returns `true`, rebuilding the hash function once
+    /// for the entries which remain.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&KOwned, &mut V) -> bool,
+    {
+        let kept = self
+            .drain()
+            .filter_map(|(key, mut value)| f(&key, &mut value).then_some((key, value)))
+            .collect::<Vec<_>>();
 
-    #[test]
-    fn it_works() {
-        let mut hashmap: PhMap<&str, &str, str> = PhMap::default();
+        if !kept.is_empty() {
+            self.extend(kept);
+        }
+    }
 
-        let kvs = [
-            ("foo1", "bar"),
-            ("foo2", "baz"),
-            ("foo3", "bar"),
-            ("foo4", "qux"),
-            ("foo5", "foobar"),
-            ("foo6", "bazqux"),
-        ];
-        hashmap.extend(kvs.iter().copied());
+    /// Removes `key` from the map, returning its value. The value is dropped and its slot is
+    /// marked as unoccupied without rebuilding the hash function, and the slot is reclaimed by
+    /// the next rebuild or [`PhMap::maintain`]. The stored key is replaced by the last key, so
+    /// that this takes constant time, which moves the last key to the removed key's place in
+    /// iteration order.
+    pub fn remove<K>(&mut self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = self.find_index(key.as_ref())?;
+        let key_index = self.key_indices[idx] as usize;
 
-        for (k, v) in kvs {
-            assert_eq!(unsafe { hashmap.get_unchecked(k) }, &v);
+        self.keys.swap_remove(key_index);
+        if let Some(moved) = self.keys.get(key_index) {
+            let moved_idx = unsafe { self.to_index.get(moved.as_ref()).unwrap_unchecked() };
+            self.key_indices[moved_idx] = key_index as u32;
         }
+
+        self.occupied.remove(idx);
+        self.removed += 1;
+        self.values[idx].take()
+    }
+
+    /// Replaces the value for `key` if it's in the map, returning the old value. Unlike
+    /// [`PhMap::insert`], this never adds a key.
+    pub fn replace<K>(&mut self, key: &K, value: V) -> Option<V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        Some(std::mem::replace(self.get_mut(key)?, value))
+    }
+
+    /// Swaps the values for `k1` and `k2`, returning `false` without changing the map if
+    /// either is missing.
+    pub fn swap_values<K1, K2>(&mut self, k1: &K1, k2: &K2) -> bool
+    where
+        K1: ?Sized + AsRef<KRef>,
+        K2: ?Sized + AsRef<KRef>,
+    {
+        match (self.find_index(k1.as_ref()), self.find_index(k2.as_ref())) {
+            (Some(idx1), Some(idx2)) => {
+                self.values.swap(idx1, idx2);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The slot index that `key`'s value is stored at, which can be passed to
+    /// [`PhMap::get_by_index`] to look the entry up again without hashing. Indices stay valid
+    /// until the map is next rebuilt.
+    #[inline]
+    pub fn index_of<K>(&self, key: &K) -> Option<usize>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.find_index(key.as_ref())
+    }
+
+    /// Whether slot `idx` holds a value. Slots past [`PhMap::slot_count`] are unoccupied.
+    #[inline]
+    pub fn is_slot_occupied(&self, idx: usize) -> bool {
+        self.occupied.contains(idx)
+    }
+
+    /// The entry stored in slot `idx`, if there is one.
+    #[inline]
+    pub fn get_by_index(&self, idx: usize) -> Option<(&KOwned, &V)> {
+        if !self.occupied.contains(idx) {
+            return None;
+        }
+
+        let key = &self.keys[self.key_indices[idx] as usize];
+
+        Some((key, self.values[idx].as_ref()?))
+    }
+
+    /// Whether `key` is in the map. This does the same checks as [`PhMap::get`], but doesn't
+    /// read the value.
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+    pub fn contains_key<K>(&self, key: &K) -> bool
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.find_index(key.as_ref()).is_some()
+    }
+
+    /// Like [`PhMap::get_unchecked`], but also returns the slot index the value is stored at.
+    ///
+    /// # Safety
+    /// `key` must be in the map.
+    #[inline]
+    pub unsafe fn get_unchecked_with_index<K>(&self, key: &K) -> (usize, &V)
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = unsafe { self.to_index.get(key.as_ref()).unwrap_unchecked() };
+        (idx, unsafe {
+            self.values.get_unchecked(idx).as_ref().unwrap_unchecked()
+        })
+    }
+
+    /// Like [`PhMap::get_unchecked_mut`], but also returns the slot index the value is stored
+    /// at.
+    ///
+    /// # Safety
+    /// `key` must be in the map.
+    #[inline]
+    pub unsafe fn get_unchecked_mut_with_index<K>(&mut self, key: &K) -> (usize, &mut V)
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = unsafe { self.to_index.get(key.as_ref()).unwrap_unchecked() };
+        (idx, unsafe {
+            self.values
+                .get_unchecked_mut(idx)
+                .as_mut()
+                .unwrap_unchecked()
+        })
+    }
+
+    /// Iterates over all entries, in the order they were inserted.
+    pub fn iter(&self) -> Iter<'_, KOwned, V, KRef, H> {
+        Iter {
+            inner: self.iter_indexed(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Iterates over all keys, in the order they were inserted.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &KOwned> + ExactSizeIterator {
+        self.keys.iter()
+    }
+
+    /// Like [`PhMap::keys`], but borrows each key as `KRef`.
+    pub fn key_refs(&self) -> impl DoubleEndedIterator<Item = &KRef> + ExactSizeIterator {
+        self.keys.iter().map(AsRef::as_ref)
+    }
+
+    /// Consumes the map, returning its keys in the order they were inserted.
+    pub fn into_keys(self) -> impl DoubleEndedIterator<Item = KOwned> + ExactSizeIterator {
+        self.keys.into_iter()
+    }
+
+    /// Consumes the map, returning its values in slot order. Unlike [`PhMap::into_iter`], this
+    /// doesn't need to hash the keys.
+    pub fn into_values(self) -> impl Iterator<Item = V> {
+        self.values.into_iter().flatten()
+    }
+
+    /// Iterates over all entries, in the order they were inserted, allowing values to be
+    /// modified.
+    pub fn iter_mut(&mut self) -> IterMut<'_, KOwned, V, KRef, H> {
+        IterMut {
+            keys: self.keys.iter(),
+            values: NonNull::from(self.values.as_mut_slice()).cast(),
+            to_index: &self.to_index,
+            _values: PhantomData,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Iterates over all entries along with the slot index that each value is stored at.
+    pub fn iter_indexed(&self) -> IterIndexed<'_, KOwned, V, KRef, H> {
+        IterIndexed {
+            keys: self.keys.iter(),
+            values: &self.values,
+            to_index: &self.to_index,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Builds a reverse index from values to keys. The index borrows the map, so it
+    /// cannot become stale.
+    pub fn inverse_index(&self) -> InverseIndex<'_, KOwned, V>
+    where
+        V: Hash + Eq,
+    {
+        InverseIndex::new(self)
+    }
+
+    /// Builds a fixed ordering of the entries by `sort_key`, so that ranked traversal
+    /// doesn't need to re-sort on each call.
+    pub fn order_by<S, F>(&self, sort_key: F) -> OrderedIndex<'_, KOwned, V>
+    where
+        S: Ord,
+        F: FnMut(&KOwned, &V) -> S,
+    {
+        OrderedIndex::new(self, sort_key)
+    }
+
+    /// Leaks the map, returning a shared reference which lives for the rest of the program.
+    /// This is intended for process-lifetime tables stored in a `static`.
+    pub fn leak(self) -> &'static Self
+    where
+        Self: 'static,
+    {
+        Box::leak(Box::new(self))
+    }
+
+    pub fn get_copied<K>(&self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<KRef>,
+        V: Copy,
+    {
+        self.get(key).copied()
+    }
+
+    pub fn get_cloned<K>(&self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<KRef>,
+        V: Clone,
+    {
+        self.get(key).cloned()
+    }
+}
+
+impl<KOwned, V, KRef, H> Extend<(KOwned, V)> for PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (KOwned, V)>,
+    {
+        if let Err(e) = self.try_extend(kv) {
+            build_failed(e);
+        }
+    }
+}
+
+impl<'a, KOwned, V, KRef, H> Extend<(&'a KOwned, &'a V)> for PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef> + Clone,
+    V: Clone,
+    H: BuildSeededHasher + Default,
+{
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (&'a KOwned, &'a V)>,
+    {
+        self.extend(kv.into_iter().map(|(k, v)| (k.clone(), v.clone())))
+    }
+}
+
+/// Borrows the keys, as if each were wrapped in [`Cow::Borrowed`].
+impl<'a, V> Extend<(&'a str, V)> for PhCowStrMap<'a, V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (&'a str, V)>,
+    {
+        self.extend(kv.into_iter().map(|(k, v)| (Cow::Borrowed(k), v)))
+    }
+}
+
+/// Borrows the keys, as if each were wrapped in [`Cow::Borrowed`].
+impl<'a, V> Extend<(&'a [u8], V)> for PhCowBytesMap<'a, V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (&'a [u8], V)>,
+    {
+        self.extend(kv.into_iter().map(|(k, v)| (Cow::Borrowed(k), v)))
+    }
+}
+
+impl<KOwned, V, KRef, H> IntoIterator for PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher,
+{
+    type Item = (KOwned, V);
+    type IntoIter = IntoIter<KOwned, V, KRef, H>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            keys: self.keys.into_iter(),
+            values: self.values,
+            to_index: self.to_index,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, KOwned, V, KRef, H> IntoIterator for &'a PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    type Item = (&'a KOwned, &'a V);
+    type IntoIter = Iter<'a, KOwned, V, KRef, H>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, KOwned, V, KRef, H> IntoIterator for &'a mut PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    type Item = (&'a KOwned, &'a mut V);
+    type IntoIter = IterMut<'a, KOwned, V, KRef, H>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, KOwned, V, KRef, H> Index<&K> for PhMap<KOwned, V, KRef, H>
+where
+    K: ?Sized + AsRef<KRef>,
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key not in map")
+    }
+}
+
+impl<K, KOwned, V, KRef, H> IndexMut<&K> for PhMap<KOwned, V, KRef, H>
+where
+    K: ?Sized + AsRef<KRef>,
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    fn index_mut(&mut self, key: &K) -> &mut V {
+        self.get_mut(key).expect("key not in map")
+    }
+}
+
+impl<K, V> Index<&K> for PhStrMap<V>
+where
+    K: ?Sized + AsRef<str>,
+{
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key not in map")
+    }
+}
+
+impl<K, V> IndexMut<&K> for PhStrMap<V>
+where
+    K: ?Sized + AsRef<str>,
+{
+    fn index_mut(&mut self, key: &K) -> &mut V {
+        self.get_mut(key).expect("key not in map")
+    }
+}
+
+impl<KOwned, V, KRef, H, const N: usize> From<[(KOwned, V); N]> for PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    fn from(kvs: [(KOwned, V); N]) -> Self {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<KOwned, V, KRef, H> FromIterator<(KOwned, V)> for PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    /// Builds the map with a single rebuild, like [`Extend::extend`].
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (KOwned, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<V> FromIterator<(String, V)> for PhStrMap<V> {
+    /// Computes each shard's selection and builds its function once over all of the entries.
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (String, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<V> FromIterator<(&'static str, V)> for PhStrMap<V> {
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (&'static str, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+/// # Safety
+/// `to_index` must have been created with `key` as one of its keys, and `vals` must have a length
+/// of at least the maxmimum value that `to_index` can return.
+pub unsafe fn take_unchecked<K, V, H>(vals: &mut [Option<V>], to_index: &Function<H>, key: &K) -> V
+where
+    K: ?Sized + Hash,
+    H: BuildSeededHasher,
+{
+    let idx = unsafe { to_index.get(key).unwrap_unchecked() };
+    unsafe { vals.get_unchecked_mut(idx).take().unwrap_unchecked() }
+}
+
+/// The slot `to_index` gives `key`, if that slot is occupied and its fingerprint matches
+/// `hash`, the value returned by [`PhMap::hash_key`] for `key`. This is the lookup of every
+/// map which keeps a fingerprint per slot.
+#[inline]
+fn find_slot<K, H>(
+    to_index: &SlotFunction<H>,
+    top_level_hashes: &Fingerprints,
+    occupied: &Occupancy,
+    key: &K,
+    hash: u64,
+) -> Option<usize>
+where
+    K: ?Sized + Hash,
+    H: BuildSeededHasher,
+{
+    let idx = to_index.get_with_top_level_hash(key, hash)?;
+    let found = top_level_hashes.matches(idx, hash) & occupied.contains(idx);
+
+    // Whether a key is present depends on the workload rather than the code path, so select
+    // the result instead of branching on it.
+    std::hint::select_unpredictable(found, Some(idx), None)
+}
+
+#[cold]
+#[inline(never)]
+fn build_failed(e: BuildError) -> ! {
+    panic!("{e}")
+}
+
+/// A function over no keys, which doesn't allocate.
+fn empty_function<K, H>() -> Function<H>
+where
+    K: ?Sized + Hash,
+    H: BuildSeededHasher + Default,
+{
+    let keys: &[&K] = &[];
+
+    Function::with_slice_p_hash_sc(
+        keys,
+        &ph::phast::Params::new(BitsFast(0), ph::phast::bits_per_seed_to_100_bucket_size(0)),
+        H::default(),
+        ph::phast::SeedOnly,
+    )
+}
+
+fn build_function<K, H>(keys: Vec<K>, profile: Profile) -> Function<H>
+where
+    K: Hash,
+    H: BuildSeededHasher + Default,
+{
+    let params = profile.params(keys.len());
+
+    Function::with_vec_p_hash_sc(keys, &params, H::default(), ph::phast::SeedOnly)
+}
+
+/// A function over a set of keys, and the slot it places each of them in.
+struct SlotLayout<H = KeyHasher> {
+    to_index: SlotFunction<H>,
+    /// The slot of each key, in the order the keys were given.
+    indices: Vec<usize>,
+    /// One more than the largest slot.
+    num_slots: usize,
+}
+
+impl<H> SlotLayout<H>
+where
+    H: BuildSeededHasher + Default,
+{
+    /// Builds a function over `keys`, whose hashes from [`PhMap::hash_key`] are `hashes` in
+    /// the same order. The slot for every key is known as soon as the function is built, so
+    /// they're all found up front and the slot storage can be allocated once. If `verify` is
+    /// set, keys which share a slot are an error rather than only a debug assertion.
+    fn new<'k, K, I>(
+        keys: I,
+        hashes: &[u64],
+        profile: Profile,
+        verify: bool,
+    ) -> Result<Self, BuildError>
+    where
+        K: ?Sized + Hash + 'k,
+        I: Iterator<Item = &'k K> + Clone,
+    {
+        let (to_index, indices) = SlotFunction::build(keys, hashes, profile);
+
+        let mut num_slots = 0;
+        let indices = indices
+            .into_iter()
+            .enumerate()
+            .map(|(key_index, idx)| {
+                let idx = idx.ok_or(BuildError::UnplacedKey { key_index })?;
+                num_slots = num_slots.max(idx + 1);
+
+                Ok(idx)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        check_slots(&indices, num_slots, verify)?;
+
+        Ok(Self {
+            to_index,
+            indices,
+            num_slots,
+        })
+    }
+}
+
+// The helpers below don't depend on the key, value or hasher types, so they're split out of
+// the generic code to be compiled once rather than for every kind of map.
+
+/// Checks that no two keys were placed in the same slot, where `indices` is the slot of each
+/// key and every slot is below `num_slots`. Without `verify`, this is only a debug assertion.
+fn check_slots(indices: &[usize], num_slots: usize, verify: bool) -> Result<(), BuildError> {
+    if verify {
+        let mut used = vec![false; num_slots];
+        for (key_index, &slot) in indices.iter().enumerate() {
+            if std::mem::replace(&mut used[slot], true) {
+                return Err(BuildError::SharedSlot { key_index, slot });
+            }
+        }
+    } else {
+        debug_assert!(indices.iter().all_unique());
+    }
+
+    Ok(())
+}
+
+/// Resizes the per-slot hashes, key indices and occupancy to `num_slots`, and fills them in
+/// for keys placed in `indices` with `hashes`, both in key order. Every index must be below
+/// `num_slots`, and there must be at most `u32::MAX` keys.
+fn place_slots(
+    top_level_hashes: &mut Fingerprints,
+    key_indices: &mut Vec<u32>,
+    occupied: &mut Occupancy,
+    indices: &[usize],
+    hashes: &[u64],
+    num_slots: usize,
+) {
+    key_indices.clear();
+    top_level_hashes.reset(num_slots);
+    key_indices.resize(num_slots, 0);
+    occupied.reset(num_slots);
+
+    for (key_index, (&idx, &hash)) in indices.iter().zip(hashes).enumerate() {
+        occupied.insert(idx);
+        unsafe {
+            top_level_hashes.set_unchecked(idx, hash);
+            *key_indices.get_unchecked_mut(idx) = key_index as u32;
+        }
+    }
+}
+
+/// The number of keys in a map with `existing` keys after adding `new` keys. Slots store key
+/// indices as `u32`, so this is checked before building a function for more keys than that.
+fn checked_num_keys(existing: usize, new: usize) -> Result<usize, BuildError> {
+    let num_keys = existing.saturating_add(new);
+
+    match u32::try_from(num_keys) {
+        Ok(_) => Ok(num_keys),
+        Err(_) => Err(BuildError::TooManyKeys { keys: num_keys }),
+    }
+}
+
+/// PHast stores each seed in at most 16 bits, so there is no benefit to going above this
+/// regardless of the number of keys.
+const MAX_SEED_BITS: u8 = 16;
+
+/// Bits per seed for a function over `num_keys` keys, `ceil(log2(num_keys)) + 1` capped at
+/// [`MAX_SEED_BITS`]. Unlike `next_power_of_two`, this cannot overflow for any `num_keys`.
+fn seed_bits(num_keys: usize) -> u8 {
+    let ceil_log2 = usize::BITS - num_keys.saturating_sub(1).leading_zeros();
+
+    (ceil_log2 + 1).min(MAX_SEED_BITS.into()) as u8
+}
+
+/// The smallest range of bytes, after the prefix common to all of `strs`, which is different
+/// for every string. Strings too short to contain a range are treated as distinct from every
+/// other string. If the strings are not all unique, the range extends to the end of the
+/// longest string.
+fn smallest_uncommon_range<'a, I>(strs: I) -> Range<usize>
+where
+    I: IntoIterator<Item = &'a [u8]>,
+    I::IntoIter: Clone,
+{
+    let strs = strs.into_iter();
+    let max_len = strs.clone().map(<[u8]>::len).max().unwrap_or(0);
+    let mut start = 0;
+
+    while start < max_len && strs.clone().map(|s| s.get(start)).all_equal() {
+        start += 1;
+    }
+
+    // Every byte is shared, as there's only one key, so fall back to the whole key rather
+    // than an empty range which any key would match.
+    if start == max_len {
+        return 0..max_len;
+    }
+
+    let mut out = start..(start + 1).min(max_len);
+    while out.end < max_len && !strs.clone().map(|s| s.get(out.clone())).all_unique() {
+        out.end += 1;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::hash::{Hash as _, Hasher as _};
+
+    use ph::BuildSeededHasher;
+
+    use super::{
+        Fingerprints, InlineBytes, MAX_SEED_BITS, checked_num_keys, seed_bits,
+        smallest_uncommon_range,
+    };
+    use crate::inline_bytes::INLINE_CAPACITY;
+    use crate::key_compression::Selection;
+    use crate::{
+        BuildError, Codec, Duplicates, FrozenPhMap, KeyCompression, LookupResult, MapOptions,
+        PerfectMap, Ph2Map, PhBiMap, PhBytesKey, PhBytesMap, PhCompressedMap, PhCowBytesMap,
+        PhCowStrMap, PhEnumMap, PhIndexMap, PhIntMap, PhInternedMap, PhInverseMap, PhKey, PhMap,
+        PhMapBuilder, PhPairMap, PhPathMap, PhPrefixMap, PhSet, PhShardedMap, PhStrInterner,
+        PhStrMap, PhStrSet, PhTaggedMap, Profile, RunLength, RunLengthError,
+    };
+
+    /// `TEST_PHF_MAP`, a `phf::Map` generated by the build script.
+    mod codegen {
+        include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
+    }
+
+    #[test]
+    fn it_works() {
+        let mut hashmap: PhMap<&str, &str, str> = PhMap::default();
+
+        let kvs = [
+            ("foo1", "bar"),
+            ("foo2", "baz"),
+            ("foo3", "bar"),
+            ("foo4", "qux"),
+            ("foo5", "foobar"),
+            ("foo6", "bazqux"),
+        ];
+        hashmap.extend(kvs.iter().copied());
+
+        for (k, v) in kvs {
+            assert_eq!(unsafe { hashmap.get_unchecked(k) }, &v);
+        }
+    }
+
+    #[test]
+    fn from_array() {
+        let kvs = [("foo1", "bar"), ("foo2", "baz"), ("foo3", "bar")];
+        let hashmap: PhMap<&str, &str, str> = PhMap::from(kvs);
+
+        assert_eq!(hashmap.len(), kvs.len());
+        for (k, v) in kvs {
+            assert_eq!(hashmap.get(k), Some(&v));
+        }
+        assert_eq!(hashmap.get("foo4"), None);
+        assert_eq!(hashmap.rebuild_stats().rebuilds, 1);
+    }
+
+    #[test]
+    fn entry_or_default() {
+        let mut hashmap: PhMap<&str, u32, str> = PhMap::default();
+
+        *hashmap.entry("foo").or_default() += 1;
+        *hashmap.entry("bar").or_insert(5) += 1;
+        *hashmap.entry("foo").or_default() += 1;
+        *hashmap.get_or_insert_with("bar", || 0) += 1;
+        *hashmap.get_or_insert_with("baz", || 10) += 1;
+
+        assert_eq!(hashmap.get("foo"), Some(&2));
+        assert_eq!(hashmap.get("bar"), Some(&7));
+        assert_eq!(hashmap.get("baz"), Some(&11));
+        assert_eq!(hashmap.rebuild_stats().rebuilds, 3);
+    }
+
+    #[test]
+    fn insert_returns_previous_value() {
+        let mut map = PhMap::<String, u32, str>::default();
+
+        assert_eq!(map.insert("a".to_owned(), 1), None);
+        assert_eq!(map.insert("a".to_owned(), 2), Some(1));
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.rebuild_stats().rebuilds, 1);
+    }
+
+    #[test]
+    fn replace_and_swap_values() {
+        let mut map = PhMap::<String, u32, str>::from([("a".to_owned(), 1), ("b".to_owned(), 2)]);
+
+        assert_eq!(map.replace("a", 10), Some(1));
+        assert_eq!(map.replace("c", 30), None);
+        assert!(!map.contains_key("c"));
+
+        assert!(map.swap_values("a", "b"));
+        assert!(!map.swap_values("a", "c"));
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.get("b"), Some(&10));
+        assert_eq!(map.rebuild_stats().rebuilds, 1);
+    }
+
+    #[test]
+    fn entry_batch() {
+        let mut map = PhMap::<String, u32, str>::from([("a".to_owned(), 1)]);
+
+        let mut batch = map.entry_batch();
+        *batch.or_insert_with("a".to_owned(), || 10) += 1;
+        *batch.or_insert_with("b".to_owned(), || 20) += 1;
+        *batch.or_default("b".to_owned()) += 1;
+        batch.or_insert("c".to_owned(), 30);
+        batch.commit();
+
+        assert_eq!(map.rebuild_stats().rebuilds, 2);
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.get("b"), Some(&22));
+        assert_eq!(map.get("c"), Some(&30));
+    }
+
+    #[test]
+    fn extend_borrowed() {
+        let kvs = [("foo".to_owned(), 1), ("bar".to_owned(), 2)];
+
+        let mut hashmap: PhMap<String, u32, str> = PhMap::default();
+        hashmap.extend(kvs.iter().map(|(k, v)| (k, v)));
+
+        assert_eq!(hashmap.get("foo"), Some(&1));
+        assert_eq!(hashmap.get("bar"), Some(&2));
+    }
+
+    #[test]
+    fn iter() {
+        let map = PhMap::<String, u32, str>::from([
+            ("a".to_owned(), 1),
+            ("b".to_owned(), 2),
+            ("c".to_owned(), 3),
+        ]);
+
+        assert_eq!(map.len(), 3);
+        assert!(!map.is_empty());
+        assert!(map.contains_key("b"));
+        assert!(!map.contains_key("d"));
+        assert_eq!(map["c"], 3);
+        assert_eq!(map.iter().len(), 3);
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "b", "c"]);
+        assert_eq!(map.key_refs().rev().collect::<Vec<_>>(), ["c", "b", "a"]);
+        assert_eq!(
+            map.iter()
+                .map(|(k, v)| (k.as_str(), *v))
+                .collect::<Vec<_>>(),
+            vec![("a", 1), ("b", 2), ("c", 3)]
+        );
+    }
+
+    #[test]
+    #[should_panic = "key not in map"]
+    fn index_missing_key() {
+        let map = PhMap::<String, u32, str>::from([("a".to_owned(), 1)]);
+
+        let _ = map["b"];
+    }
+
+    #[test]
+    fn debug() {
+        let map = PhMap::<String, u32, str>::from([("a".to_owned(), 1), ("b".to_owned(), 2)]);
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("a".to_owned(), 1)]);
+
+        assert_eq!(format!("{map:?}"), r#"{"a": 1, "b": 2}"#);
+        assert!(format!("{map:#?}").contains("slots"));
+        assert_eq!(format!("{str_map:?}"), r#"{"a": 1}"#);
+    }
+
+    #[test]
+    fn reserve_slots() {
+        let mut map = PhMap::<String, u32, str>::default();
+        map.reserve_slots(1000);
+        let values = map.values.as_ptr();
+
+        map.extend((0..100).map(|i| (i.to_string(), i)));
+        map.extend((100..200).map(|i| (i.to_string(), i)));
+
+        assert_eq!(map.values.as_ptr(), values);
+        assert!(map.values.capacity() >= 1000);
+
+        map.shrink_to_fit();
+
+        assert_eq!(map.values.capacity(), map.slot_count());
+    }
+
+    #[test]
+    fn compact_values() {
+        let mut map: PhMap<String, u32, str> = (0..10).map(|i| (i.to_string(), i)).collect();
+        map.remove("3");
+
+        let mut dense_values = map.values_dense().copied().collect::<Vec<_>>();
+        let mut dense = map.compact_values();
+
+        assert_eq!(dense.values(), dense_values);
+        assert_eq!(dense.get("4"), Some(&4));
+        assert_eq!(dense.get("3"), None);
+
+        dense.values_mut().iter_mut().for_each(|v| *v += 1);
+        dense_values.sort();
+
+        assert_eq!(dense.get("4"), Some(&5));
+        assert_eq!(dense_values, [0, 1, 2, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn index_map() {
+        let mut map: PhIndexMap<String, u32, str> =
+            ["c", "a", "b"].iter().map(|k| (k.to_string(), 0)).collect();
+        map.extend([
+            ("d".to_owned(), 1),
+            ("a".to_owned(), 2),
+            ("d".to_owned(), 3),
+        ]);
+
+        assert_eq!(map.insert("b".to_owned(), 4), Some(0));
+        assert_eq!(map.insert("e".to_owned(), 5), None);
+        assert_eq!(map.keys(), ["c", "a", "b", "d", "e"]);
+        assert_eq!(map.values(), [0, 2, 4, 3, 5]);
+
+        assert_eq!(map.get_full("d"), Some((3, &"d".to_owned(), &3)));
+        assert_eq!(map.get_index(1), Some((&"a".to_owned(), &2)));
+        assert_eq!(map.get_index(5), None);
+        assert_eq!(map.get("f"), None);
+    }
+
+    #[test]
+    fn int_map() {
+        let mut dense = (-5..5).map(|i| (i, i * 2)).collect::<PhIntMap<i32, i32>>();
+        assert!(dense.is_dense());
+        assert_eq!(dense.get(-5), Some(&-10));
+        assert_eq!(dense.get(5), None);
+        assert_eq!(dense.insert(4, 0), Some(8));
+        assert_eq!(dense.remove(0), Some(0));
+        assert_eq!(dense.len(), 9);
+        assert_eq!(dense.iter().next(), Some((-5, &-10)));
+
+        let mut sparse = (0..100u64)
+            .map(|i| (i << 40, i))
+            .collect::<PhIntMap<_, _>>();
+        assert!(!sparse.is_dense());
+        assert!((0..100).all(|i| sparse.get(i << 40) == Some(&i)));
+        assert_eq!(sparse.get(1), None);
+        assert_eq!(sparse.get(u64::MAX), None);
+
+        sparse.extend([(1, 1), (1, 2), (0, 3)]);
+        assert_eq!(sparse.len(), 101);
+        assert_eq!(sparse.get(1), Some(&2));
+        assert_eq!(sparse.get(0), Some(&3));
+        assert_eq!(sparse.iter().count(), 101);
+    }
+
+    #[test]
+    fn frozen() {
+        let mut map: PhMap<String, u32, str> = (0..10).map(|i| (i.to_string(), i)).collect();
+        map.remove("3");
+
+        let mut frozen = map.freeze();
+        assert_eq!(frozen.len(), 9);
+        assert_eq!(frozen.get("4"), Some(&4));
+        assert_eq!(frozen.get("3"), None);
+        assert!(!frozen.contains_key("10"));
+
+        *frozen.get_mut("4").unwrap() += 1;
+        assert_eq!(frozen.values().sum::<u32>(), 43);
+
+        let collected = [("a", 1), ("b", 2)]
+            .into_iter()
+            .collect::<FrozenPhMap<str, _>>();
+        assert_eq!(collected.get("b"), Some(&2));
+    }
+
+    #[test]
+    fn frozen_thaw() {
+        use crate::{IntegrityError, PerfectMap};
+
+        fn lookup<M: PerfectMap<str, Value = u32>>(map: &M, key: &str) -> Option<u32> {
+            map.get(key).copied()
+        }
+
+        let map: PhMap<String, u32, str> = (0..10).map(|i| (i.to_string(), i)).collect();
+        let frozen = map.freeze();
+        assert_eq!(lookup(&frozen, "7"), Some(7));
+        assert_eq!(lookup(&frozen, "10"), None);
+        assert_eq!(PerfectMap::len(&frozen), 10);
+        assert_eq!(PerfectMap::values(&frozen).count(), 10);
+
+        let keys = (0..10).rev().map(|i| i.to_string());
+        let mut thawed = frozen.thaw(keys).unwrap();
+        assert_eq!(thawed.verify(), Ok(()));
+        assert_eq!(thawed.keys().next().map(String::as_str), Some("9"));
+        assert_eq!(thawed.get("3"), Some(&3));
+
+        thawed.insert("10".to_owned(), 10);
+        assert_eq!(thawed.get("10"), Some(&10));
+        assert_eq!(thawed.get("0"), Some(&0));
+
+        let frozen = || {
+            thawed
+                .iter()
+                .map(|(k, v)| (k.clone(), *v))
+                .collect::<PhMap<String, u32, str>>()
+                .freeze()
+        };
+        assert_eq!(
+            frozen().thaw(["1".to_owned()]).err(),
+            Some(IntegrityError::KeyCountMismatch { keys: 1, len: 11 })
+        );
+        assert!(matches!(
+            frozen().thaw((1..12).map(|i| i.to_string())),
+            Err(IntegrityError::MissingSlot { key_index: 10 })
+        ));
+        assert!(matches!(
+            frozen().thaw((0..11).map(|i| (i % 10).to_string())),
+            Err(IntegrityError::DuplicateKey { key_index: 10, .. })
+        ));
+    }
+
+    #[test]
+    fn bi_map() {
+        let mut map: PhBiMap<String, String, str, str> = [("one", "1"), ("two", "2")]
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect();
+
+        assert!(map.insert("three".to_owned(), "3".to_owned()));
+        assert!(!map.insert("three".to_owned(), "4".to_owned()));
+        assert!(!map.insert("four".to_owned(), "1".to_owned()));
+        map.extend([
+            ("five".to_owned(), "5".to_owned()),
+            ("six".to_owned(), "5".to_owned()),
+        ]);
+
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get_by_key("two").map(String::as_str), Some("2"));
+        assert_eq!(map.get_by_value("5").map(String::as_str), Some("five"));
+        assert_eq!(map.get_by_value("4"), None);
+        assert!(!map.contains_key("six"));
+        assert_eq!(
+            map.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(),
+            ["one", "two", "three", "five"]
+        );
+    }
+
+    #[test]
+    fn cow_map() {
+        let text = String::from("alpha beta gamma");
+
+        let mut str_map = PhCowStrMap::default();
+        str_map.extend(text.split(' ').zip(0..));
+        str_map.insert(Cow::Owned(format!("{}-{}", "delta", 3)), 3);
+
+        assert_eq!(str_map.get("beta"), Some(&1));
+        assert_eq!(str_map.get("delta-3"), Some(&3));
+        assert!(matches!(
+            str_map.iter().next(),
+            Some((Cow::Borrowed("alpha"), 0))
+        ));
+
+        let mut bytes_map = PhCowBytesMap::default();
+        bytes_map.extend([(text.as_bytes(), 0)]);
+        assert_eq!(bytes_map.get(text.as_bytes()), Some(&0));
+    }
+
+    #[test]
+    fn sharded_map() {
+        let mut map = PhShardedMap::<String, u32, str>::with_shards(4);
+        map.extend((0..100).map(|i| (i.to_string(), i)));
+        map.extend_parallel((100..200).map(|i| (i.to_string(), i)));
+
+        assert_eq!(map.len(), 200);
+        assert!(map.shards().iter().all(|shard| !shard.is_empty()));
+        assert!((0..200).all(|i| map.get(&i.to_string()) == Some(&i)));
+        assert_eq!(map.get("200"), None);
+
+        assert_eq!(map.insert("7".to_owned(), 0), Some(7));
+        assert_eq!(map.remove("8"), Some(8));
+        assert_eq!(map.iter().count(), 199);
+    }
+
+    #[test]
+    fn index_of() {
+        let mut map: PhMap<String, u32, str> = (0..10).map(|i| (i.to_string(), i)).collect();
+        let idx = map.index_of("7").unwrap();
+
+        assert_eq!(map.get_by_index(idx), Some((&"7".to_owned(), &7)));
+        assert_eq!(map.index_of("10"), None);
+
+        map.remove("2");
+        assert_eq!(map.get_by_index(idx), Some((&"7".to_owned(), &7)));
+        // The last key takes the place of the removed one.
+        assert!(map.keys().eq(["0", "1", "9", "3", "4", "5", "6", "7", "8"]));
+        let moved = map.index_of("9").unwrap();
+        assert_eq!(map.get_by_index(moved), Some((&"9".to_owned(), &9)));
+
+        map.remove("7");
+        assert_eq!(map.get_by_index(idx), None);
+        assert!(!map.is_slot_occupied(idx));
+        assert!(!map.is_slot_occupied(map.slot_count()));
+        assert_eq!(
+            (0..map.slot_count())
+                .filter(|idx| map.is_slot_occupied(*idx))
+                .count(),
+            8
+        );
+    }
+
+    #[test]
+    fn memory_usage() {
+        let map: PhMap<String, u64, str> = (0..100).map(|i| (format!("{i:03}"), i)).collect();
+        let usage = map.memory_usage();
+
+        assert!(map.slot_count() >= 100);
+        assert!(usage.hashes >= map.slot_count() * size_of::<u64>());
+        assert!(usage.keys >= 100 * (size_of::<String>() + 3));
+
+        let long = [7; INLINE_CAPACITY + 1];
+        let map: PhMap<InlineBytes, u64, [u8]> = PhMap::from([
+            (InlineBytes::from(&b"ab"[..]), 1),
+            (InlineBytes::from(&long[..]), 2),
+        ]);
+        let usage = map.memory_usage();
+        assert_eq!(
+            usage.keys,
+            map.keys.capacity() * size_of::<InlineBytes>()
+                + map.key_indices.capacity() * size_of::<u32>()
+                + long.len()
+        );
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut map = PhMap::<String, String, str>::from([
+            ("a".to_owned(), " One ".to_owned()),
+            ("b".to_owned(), "TWO".to_owned()),
+        ]);
+
+        for (_, value) in &mut map {
+            *value = value.trim().to_lowercase();
+        }
+
+        assert_eq!(map.get("a").map(String::as_str), Some("one"));
+        assert_eq!(map.get("b").map(String::as_str), Some("two"));
+    }
+
+    #[test]
+    fn into_iter() {
+        let map = PhMap::<String, String, str>::from([
+            ("a".to_owned(), "one".to_owned()),
+            ("b".to_owned(), "two".to_owned()),
+            ("c".to_owned(), "three".to_owned()),
+        ]);
+
+        let mut iter = map.into_iter();
+        assert_eq!(iter.next(), Some(("a".to_owned(), "one".to_owned())));
+        assert_eq!(iter.len(), 2);
+        // The remaining entries are dropped with the iterator.
+        drop(iter);
+    }
+
+    #[test]
+    fn retain() {
+        let mut map: PhMap<String, u32, str> = (0..10).map(|i| (i.to_string(), i)).collect();
+
+        map.retain(|_, v| {
+            *v *= 10;
+            *v % 20 == 0
+        });
+
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.rebuild_stats().rebuilds, 2);
+        assert_eq!(map.get("4"), Some(&40));
+        assert_eq!(map.get("5"), None);
+    }
+
+    #[test]
+    fn into_keys_and_values() {
+        let map = || PhMap::<String, u32, str>::from([("a".to_owned(), 1), ("b".to_owned(), 2)]);
+
+        assert_eq!(map().into_keys().collect::<Vec<_>>(), ["a", "b"]);
+
+        let mut values = map().into_values().collect::<Vec<_>>();
+        values.sort();
+        assert_eq!(values, [1, 2]);
+    }
+
+    #[test]
+    fn drain() {
+        let mut map = PhMap::<String, u32, str>::from([("a".to_owned(), 1), ("b".to_owned(), 2)]);
+
+        let drained = map.drain().filter(|(_, v)| *v > 1).collect::<Vec<_>>();
+
+        assert_eq!(drained, [("b".to_owned(), 2)]);
+        assert!(map.is_empty());
+        assert_eq!(map.get("a"), None);
+    }
+
+    #[test]
+    fn from_iter() {
+        let map: PhMap<String, u32, str> = (0..10).map(|i| (i.to_string(), i)).collect();
+
+        assert_eq!(map.rebuild_stats().rebuilds, 1);
+        assert!((0..10).all(|i| map.get(&i.to_string()) == Some(&i)));
+    }
+
+    #[test]
+    fn remove_and_clear() {
+        let mut map = PhMap::<String, u32, str>::from([
+            ("a".to_owned(), 1),
+            ("b".to_owned(), 2),
+            ("c".to_owned(), 3),
+        ]);
+
+        assert_eq!(map.remove("b"), Some(2));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.get("b"), None);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.rebuild_stats().rebuilds, 1);
+
+        map.insert("d".to_owned(), 4);
+
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "c", "d"]);
+        assert_eq!(map.get("b"), None);
+        assert_eq!(map.get("d"), Some(&4));
+
+        map.clear();
+
+        assert!(map.is_empty());
+        assert_eq!(map.get("a"), None);
+    }
+
+    #[test]
+    fn iter_indexed_slots_are_unique() {
+        let hashmap: PhMap<&str, u32, str> = PhMap::from([("foo", 1), ("bar", 2), ("baz", 3)]);
+
+        let mut slots = hashmap
+            .iter_indexed()
+            .map(|(slot, k, v)| {
+                assert_eq!(hashmap.get(*k), Some(v));
+                slot
+            })
+            .collect::<Vec<_>>();
+        slots.sort();
+        slots.dedup();
+
+        assert_eq!(slots.len(), 3);
+    }
+
+    #[test]
+    fn inverse_index() {
+        let hashmap: PhMap<&str, u32, str> = PhMap::from([("foo", 1), ("bar", 2), ("baz", 1)]);
+        let inverse = hashmap.inverse_index();
+
+        let mut keys = inverse.keys_for_value(&1).to_vec();
+        keys.sort();
+        assert_eq!(keys, [&"baz", &"foo"]);
+        assert_eq!(inverse.keys_for_value(&2), [&"bar"]);
+        assert!(inverse.keys_for_value(&3).is_empty());
+    }
+
+    #[test]
+    fn inverse_map() {
+        let mut map: PhInverseMap<&str, u32, str> =
+            [("foo", 1), ("bar", 2), ("baz", 1)].into_iter().collect();
+
+        let mut keys = map.keys_for_value(&1).collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, [&"baz", &"foo"]);
+        assert_eq!(map.keys_for_value(&3).count(), 0);
+        assert_eq!(map.get("bar"), Some(&2));
+
+        map.extend([("qux", 2)]);
+        let mut keys = map.keys_for_value(&2).collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, [&"bar", &"qux"]);
+        assert_eq!(map.keys_for_value(&1).count(), 2);
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    fn append() {
+        let mut a: PhMap<&str, u32, str> = PhMap::from([("foo", 1), ("bar", 2)]);
+        let mut b: PhMap<&str, u32, str> = PhMap::from([("baz", 3)]);
+
+        a.append(&mut b);
+
+        assert_eq!(a.get("foo"), Some(&1));
+        assert_eq!(a.get("baz"), Some(&3));
+        assert_eq!(b.get("baz"), None);
+    }
+
+    #[test]
+    fn contains_all_and_any() {
+        let mut hashmap: PhMap<String, u32, str> = PhMap::default();
+        hashmap.extend((0..100).map(|i| (format!("key-{i}"), i)));
+
+        assert!(hashmap.contains_all((0..100).map(|i| format!("key-{i}"))));
+        assert!(!hashmap.contains_all((0..101).map(|i| format!("key-{i}"))));
+        assert!(hashmap.contains_any(["nope", "key-99"]));
+        assert!(!hashmap.contains_any(["nope", "also-nope"]));
+
+        hashmap.remove("key-99");
+        assert!(!hashmap.contains_any(["nope", "key-99"]));
+        assert!(!hashmap.contains_all((0..100).map(|i| format!("key-{i}"))));
+        assert!(hashmap.contains_all((0..99).map(|i| format!("key-{i}"))));
+    }
+
+    #[test]
+    fn extend_prehashed() {
+        let keys = ["foo", "bar"];
+        let hashes = PhMap::<&str, u32, str>::hash_keys(keys);
+
+        let mut hashmap: PhMap<&str, u32, str> = PhMap::default();
+        unsafe {
+            hashmap.extend_prehashed(
+                keys.into_iter()
+                    .zip(hashes)
+                    .zip([1, 2])
+                    .map(|((k, h), v)| (k, h, v)),
+            )
+        };
+
+        assert_eq!(hashmap.get("foo"), Some(&1));
+        assert_eq!(hashmap.get("bar"), Some(&2));
+    }
+
+    #[cfg(feature = "alloc-stats")]
+    #[test]
+    fn build_stats() {
+        let mut hashmap: PhMap<&str, u32, str> = PhMap::default();
+        assert_eq!(hashmap.build_stats(), Default::default());
+
+        hashmap.extend([("foo", 1), ("bar", 2)]);
+        let stats = hashmap.build_stats();
+        assert!(stats.allocations > 0);
+        assert!(stats.bytes_allocated > 0);
+    }
+
+    #[test]
+    fn rebuild_stats() {
+        let mut map = PhMap::<String, u32, str>::default();
+        map.insert("ab".to_owned(), 1);
+        map.insert("cde".to_owned(), 2);
+        map.insert("f".to_owned(), 3);
+
+        let stats = map.rebuild_stats();
+        assert_eq!(stats.rebuilds, 3);
+        assert_eq!(stats.entries_moved, 1 + 2);
+        assert_eq!(stats.bytes_rehashed, 2 + (2 + 3));
+    }
+
+    #[test]
+    fn maintain() {
+        let mut map: PhMap<String, u32, str> = (0..100).map(|i| (i.to_string(), i)).collect();
+        assert!(!map.maintain());
+
+        let slots = map.slot_count();
+        for i in 0..50 {
+            map.remove(&i.to_string());
+        }
+        assert_eq!(map.slot_count(), slots);
+
+        assert!(map.maintain());
+        assert!(map.slot_count() < slots);
+        assert!(!map.maintain());
+        assert_eq!(map.rebuild_stats().rebuilds, 2);
+        assert_eq!(map.len(), 50);
+        assert!((50..100).all(|i| map.get(&i.to_string()) == Some(&i)));
+
+        let mut str_map = PhStrMap::with_options(MapOptions::new().prefilter(0));
+        str_map.extend([("ab1", 1), ("ab2", 2), ("cd1", 3)]);
+        let hashed_bytes = str_map.compression_stats().hashed_bytes;
+
+        str_map.remove("ab2");
+        assert!(str_map.maintain());
+        assert!(!str_map.maintain());
+        assert!(str_map.compression_stats().hashed_bytes < hashed_bytes);
+        assert_eq!(str_map.get("ab1"), Some(&1));
+        assert_eq!(str_map.get("cd1"), Some(&3));
+        assert_eq!(
+            str_map.iter().collect::<Vec<_>>(),
+            [("ab1", &1), ("cd1", &3)]
+        );
+    }
+
+    #[test]
+    fn verify() {
+        let mut hashmap: PhMap<&str, u32, str> = PhMap::from([("foo", 1), ("bar", 2)]);
+        assert_eq!(hashmap.verify(), Ok(()));
+
+        let Fingerprints::U64(hashes) = &mut hashmap.top_level_hashes else {
+            unreachable!()
+        };
+        hashes.iter_mut().for_each(|hash| *hash ^= 1);
+        assert!(hashmap.verify().is_err());
+    }
+
+    #[test]
+    fn get_detailed() {
+        let hashmap: PhMap<&str, u32, str> = PhMap::from([("foo", 1), ("bar", 2)]);
+
+        assert!(matches!(
+            hashmap.get_detailed("foo"),
+            LookupResult::Found {
+                stored_key: &"foo",
+                value: &1,
+                ..
+            }
+        ));
+        assert!(hashmap.get_detailed("qux").value().is_none());
+    }
+
+    #[test]
+    fn pair_map_borrowed_lookup() {
+        let mut pair_map = PhPairMap::<String, u32, &str>::default();
+        pair_map.extend([(("foo".to_owned(), 1), "a"), (("foo".to_owned(), 2), "b")]);
+
+        assert_eq!(pair_map.get("foo", &1), Some(&"a"));
+        assert_eq!(pair_map.get("foo", &2), Some(&"b"));
+        assert!(!pair_map.contains_key("bar", &1));
+    }
+
+    #[test]
+    fn nested_map() {
+        let mut nested: Ph2Map<&str, &str, &str, str, str> = [
+            ("en", "greeting", "hello"),
+            ("en", "farewell", "goodbye"),
+            ("fr", "greeting", "bonjour"),
+        ]
+        .into_iter()
+        .collect();
+        nested.insert("fr", "farewell", "au revoir");
+
+        assert_eq!(nested.len(), 4);
+        assert_eq!(nested.get("en", "greeting"), Some(&"hello"));
+        assert_eq!(nested.get("en", "farewell"), Some(&"goodbye"));
+        assert_eq!(nested.get("fr", "greeting"), Some(&"bonjour"));
+        assert_eq!(nested.get("fr", "farewell"), Some(&"au revoir"));
+        assert!(!nested.contains_key("de", "greeting"));
+    }
+
+    #[test]
+    fn nested_map_reinsert() {
+        let mut nested: Ph2Map<&str, &str, &str, str, str> = [
+            ("en", "greeting", "hi"),
+            ("en", "greeting", "hello"),
+            ("fr", "greeting", "bonjour"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(nested.len(), 2);
+        assert_eq!(nested.get("en", "greeting"), Some(&"hello"));
+
+        nested.insert("en", "greeting", "hey");
+        nested.extend([
+            ("fr", "greeting", "salut"),
+            ("fr", "farewell", "au revoir"),
+            ("de", "greeting", "hallo"),
+        ]);
+
+        assert_eq!(nested.len(), 4);
+        assert_eq!(nested.get("en", "greeting"), Some(&"hey"));
+        assert_eq!(nested.get("fr", "greeting"), Some(&"salut"));
+        assert_eq!(nested.get("fr", "farewell"), Some(&"au revoir"));
+        assert_eq!(nested.get("de", "greeting"), Some(&"hallo"));
+    }
+
+    #[test]
+    fn order_by() {
+        let hashmap: PhMap<&str, u32, str> = PhMap::from([("foo", 3), ("bar", 1), ("baz", 2)]);
+        let ordered = hashmap.order_by(|_, v| std::cmp::Reverse(*v));
+
+        assert_eq!(ordered.top_n(2), [(&"foo", &3), (&"baz", &2)]);
+        assert_eq!(
+            ordered.iter_ordered().map(|(k, _)| *k).collect::<Vec<_>>(),
+            ["foo", "baz", "bar"]
+        );
+    }
+
+    #[test]
+    fn interned_values() {
+        let mut interned = PhInternedMap::<&str, String, str>::default();
+        interned.extend([("foo", "a".to_owned()), ("bar", "b".to_owned())]);
+        interned.insert("baz", "a".to_owned());
+
+        assert_eq!(interned.distinct_values(), ["a", "b"]);
+        assert_eq!(interned.get("foo").map(|v| &v[..]), Some("a"));
+        assert_eq!(interned.get("bar").map(|v| &v[..]), Some("b"));
+        assert_eq!(interned.get("baz").map(|v| &v[..]), Some("a"));
+
+        interned.insert("foo", "c".to_owned());
+        interned.insert("bar", "a".to_owned());
+
+        assert_eq!(interned.distinct_values(), ["a", "c"]);
+        assert_eq!(interned.get("foo").map(|v| &v[..]), Some("c"));
+        assert_eq!(interned.get("bar").map(|v| &v[..]), Some("a"));
+        assert_eq!(interned.get("baz").map(|v| &v[..]), Some("a"));
+    }
+
+    #[test]
+    fn tags_survive_rebuild() {
+        let mut tagged = PhTaggedMap::<&str, &str, u8, str>::default();
+        tagged.insert("foo", "bar", 1);
+        tagged.insert("baz", "qux", 2);
+
+        assert_eq!(tagged.get_with_tag("foo"), Some((&"bar", 1)));
+        assert_eq!(tagged.get_with_tag("baz"), Some((&"qux", 2)));
+        assert_eq!(tagged.set_tag("foo", 3), Some(1));
+        assert_eq!(tagged.tag("foo"), Some(3));
+
+        tagged.insert("foo", "quux", 4);
+        assert_eq!(tagged.get_with_tag("foo"), Some((&"quux", 4)));
+        assert_eq!(tagged.get_with_tag("baz"), Some((&"qux", 2)));
+    }
+
+    #[test]
+    fn seed_bits_for_huge_key_sets() {
+        assert_eq!(seed_bits(0), 1);
+        assert_eq!(seed_bits(1), 1);
+        assert_eq!(seed_bits(5), 4);
+        assert_eq!(seed_bits(8192), 14);
+        assert_eq!(seed_bits(usize::MAX), 16);
+
+        assert_eq!(checked_num_keys(3, 4), Ok(7));
+        assert_eq!(
+            checked_num_keys(u32::MAX as usize, 0),
+            Ok(u32::MAX as usize)
+        );
+        assert_eq!(
+            checked_num_keys(u32::MAX as usize, 1),
+            Err(BuildError::TooManyKeys {
+                keys: u32::MAX as usize + 1
+            })
+        );
+        assert_eq!(
+            checked_num_keys(usize::MAX, usize::MAX),
+            Err(BuildError::TooManyKeys { keys: usize::MAX })
+        );
+
+        let profiles = [
+            Profile::Balanced,
+            Profile::MinMemory,
+            Profile::MaxLookupSpeed,
+            Profile::FastBuild,
+            Profile::Custom {
+                bits_per_seed: Some(0),
+                bucket_size100: None,
+                base: &Profile::Balanced,
+            },
+            Profile::Custom {
+                bits_per_seed: Some(u8::MAX),
+                bucket_size100: Some(0),
+                base: &Profile::MinMemory,
+            },
+        ];
+        for profile in profiles {
+            for num_keys in [0, 1, 5, 1 << 20, u32::MAX as usize, usize::MAX] {
+                let params = profile.params(num_keys);
+                assert!((1..=MAX_SEED_BITS).contains(&params.seed_size.0));
+                assert!(params.bucket_size100 > 0);
+            }
+
+            let mut map = PhMap::<String, usize, str>::with_profile(profile);
+            map.extend((0..1000).map(|i| (i.to_string(), i)));
+            assert!((0..1000).all(|i| map.get(&i.to_string()) == Some(&i)));
+        }
+    }
+
+    #[test]
+    fn str_map_mixed_lengths() {
+        let mut str_map = PhStrMap::default();
+        str_map.extend([
+            ("a".to_owned(), 1),
+            ("bb".to_owned(), 2),
+            ("bc".to_owned(), 3),
+            ("ddd".to_owned(), 4),
+        ]);
+
+        assert_eq!(str_map.get("a"), Some(&1));
+        assert_eq!(str_map.get("bb"), Some(&2));
+        assert_eq!(str_map.get("bc"), Some(&3));
+        assert_eq!(str_map.get("ddd"), Some(&4));
+        assert_eq!(str_map.get("eeee"), None);
+        assert_eq!(str_map.len(), 4);
+        str_map["bb"] += 10;
+        assert_eq!(str_map["bb"], 12);
+        assert!(str_map.contains_key("bc"));
+        assert!(!str_map.contains_key("eeee"));
+        assert!(PhStrMap::<u32>::default().is_empty());
+
+        let mut entries = str_map.iter().collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(entries, [("a", &1), ("bb", &12), ("bc", &3), ("ddd", &4)]);
+        assert_eq!(str_map.keys().count(), 4);
+    }
+
+    #[test]
+    fn extend_generic() {
+        fn fill<M: Extend<(String, u32)>>(map: &mut M) {
+            map.extend([("a".to_owned(), 1), ("b".to_owned(), 2)]);
+        }
+
+        let mut map = PhMap::<String, u32, str>::default();
+        let mut str_map = PhStrMap::default();
+        fill(&mut map);
+        fill(&mut str_map);
+
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(str_map.get("b"), Some(&2));
+    }
+
+    #[test]
+    fn str_map_long_keys() {
+        let long_a = format!("{}a", "x".repeat(40));
+        let long_b = format!("{}b", "x".repeat(40));
+
+        let mut str_map = PhStrMap::default();
+        str_map.extend([
+            (long_a.clone(), 1),
+            (long_b.clone(), 2),
+            ("short".to_owned(), 3),
+        ]);
+
+        assert_eq!(str_map.get(&long_a), Some(&1));
+        assert_eq!(str_map.get(&long_b), Some(&2));
+        assert_eq!(str_map.get("short"), Some(&3));
+    }
+
+    #[test]
+    fn str_map_incremental_extend() {
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("abc".to_owned(), 1), ("abd".to_owned(), 2)]);
+        str_map.insert("xbc".to_owned(), 3);
+        str_map.insert("xbd".to_owned(), 4);
+
+        assert_eq!(str_map.get("abc"), Some(&1));
+        assert_eq!(str_map.get("abd"), Some(&2));
+        assert_eq!(str_map.get("xbc"), Some(&3));
+        assert_eq!(str_map.get("xbd"), Some(&4));
+        assert_eq!(str_map.len(), 4);
+    }
+
+    #[test]
+    fn str_map_short_keys() {
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("abcd".to_owned(), 1), ("abce".to_owned(), 2)]);
+
+        assert_eq!(str_map.get(""), None);
+        assert_eq!(str_map.get("abc"), None);
+        assert_eq!(str_map.get_mut("ab"), None);
+        assert!(!str_map.contains_key("a"));
+    }
+
+    #[test]
+    fn str_map_compression() {
+        let keys = ["user-0001.png", "user-0002.png", "user-0103.png"];
+
+        for (compression, selection) in [
+            (KeyCompression::Full, Selection::Range(0..13)),
+            (KeyCompression::StripPrefix, Selection::Range(6..13)),
+            (KeyCompression::StripSuffix, Selection::Range(0..9)),
+            (KeyCompression::Range, Selection::Range(6..9)),
+            (KeyCompression::Positions, Selection::Positions([8].into())),
+        ] {
+            let mut str_map = PhStrMap::with_options(MapOptions::new().compression(compression));
+            str_map.extend(keys.iter().map(|k| (k.to_string(), k.len())));
+
+            assert_eq!(str_map.inner.shards[0].selection, selection);
+            assert!(keys.iter().all(|k| str_map.get(k) == Some(&13)));
+        }
+    }
+
+    #[test]
+    fn str_map_verified() {
+        let kvs = [("cat".to_owned(), 1), ("cow".to_owned(), 2)];
+
+        let mut str_map = PhStrMap::default();
+        str_map.extend(kvs.clone());
+        assert_eq!(str_map.get("hat"), Some(&1));
+
+        let mut str_map = PhStrMap::with_options(MapOptions::new().verify_keys(true));
+        str_map.extend(kvs);
+        assert_eq!(str_map.get("cat"), Some(&1));
+        assert_eq!(str_map.get("hat"), None);
+        assert_eq!(str_map.get_mut("cot"), None);
+        assert!(!str_map.contains_key("mow"));
+        assert!(str_map.contains_key("cow"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut map = PhMap::<String, u32, str>::default();
+        map.extend([
+            ("a".to_owned(), 1),
+            ("bc".to_owned(), 2),
+            ("d".to_owned(), 3),
+        ]);
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, r#"{"a":1,"bc":2,"d":3}"#);
+
+        let decoded: PhMap<String, u32, str> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            decoded.iter().collect::<Vec<_>>(),
+            map.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(decoded.get("bc"), Some(&2));
+        assert_eq!(decoded.get("e"), None);
+
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("user-1", 1), ("user-2", 2), ("admin", 3)]);
+        let json = serde_json::to_string(&str_map).unwrap();
+
+        let decoded: PhStrMap<u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            decoded.iter().collect::<Vec<_>>(),
+            str_map.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(decoded.get("admin"), Some(&3));
+
+        let duplicated = r#"{"a":1,"b":2,"a":3}"#;
+        assert!(serde_json::from_str::<PhMap<String, u32, str>>(duplicated).is_err());
+        assert!(serde_json::from_str::<PhStrMap<u32>>(duplicated).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_deserialize_into() {
+        use crate::DeserializeInto;
+        use serde::de::DeserializeSeed;
+
+        let mut map = PhMap::<String, u32, str>::with_profile(Profile::MinMemory);
+        map.extend((0..100).map(|i| (i.to_string(), i)));
+        let keys_capacity = map.keys.capacity();
+
+        let json = r#"{"a":1,"bc":2,"d":3}"#;
+        DeserializeInto(&mut map)
+            .deserialize(&mut serde_json::Deserializer::from_str(json))
+            .unwrap();
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [
+                (&"a".to_owned(), &1),
+                (&"bc".to_owned(), &2),
+                (&"d".to_owned(), &3)
+            ]
+        );
+        assert_eq!(map.get("5"), None);
+        assert_eq!(map.profile, Profile::MinMemory);
+        assert_eq!(map.keys.capacity(), keys_capacity);
+
+        let duplicated = r#"{"x":1,"y":2,"x":3}"#;
+        assert!(
+            DeserializeInto(&mut map)
+                .deserialize(&mut serde_json::Deserializer::from_str(duplicated))
+                .is_err()
+        );
+        assert_eq!(map.get("bc"), Some(&2));
+
+        let mut str_map = PhStrMap::with_options(
+            MapOptions::new()
+                .verify_keys(true)
+                .duplicates(Duplicates::Error),
+        );
+        str_map.extend([("user-1", 1), ("xy", 4)]);
+        let two_byte_keys =
+            |str_map: &PhStrMap<u32>| str_map.inner.key_of_len(2, 0).unwrap().as_ptr();
+        let full_keys = two_byte_keys(&str_map);
+
+        DeserializeInto(&mut str_map)
+            .deserialize(&mut serde_json::Deserializer::from_str(json))
+            .unwrap();
+        assert_eq!(str_map.len(), 3);
+        assert_eq!(str_map.get("user-1"), None);
+        assert_eq!(str_map.get("xy"), None);
+        assert_eq!(str_map.get("bc"), Some(&2));
+        assert!(str_map.options().verify_keys);
+        assert_eq!(two_byte_keys(&str_map), full_keys);
+
+        // Like a `PhMap`, the map is left empty if it can't be built.
+        assert!(
+            DeserializeInto(&mut str_map)
+                .deserialize(&mut serde_json::Deserializer::from_str(duplicated))
+                .is_err()
+        );
+        assert!(str_map.is_empty());
+    }
+
+    #[test]
+    fn map_options_combine() {
+        let options = MapOptions::new()
+            .compression(KeyCompression::Full)
+            .prefilter(0)
+            .verify_keys(true)
+            .duplicates(Duplicates::KeepLast);
+
+        let mut str_map = PhStrMap::with_options(options);
+        str_map.extend([("cat", 1), ("cow", 2), ("cat", 3)]);
+        assert_eq!(str_map.options(), options);
+        assert_eq!(str_map.get("cat"), Some(&3));
+        assert_eq!(str_map.get("hat"), None);
+        assert_eq!(str_map.get("cot"), None);
+        assert_eq!(str_map.compression_stats().hashed_bytes, 2 * 3);
+
+        let mut path_map = PhPathMap::with_options(options);
+        path_map.extend([(std::path::Path::new("/a"), 1)]);
+        assert_eq!(path_map.options(), options);
+        assert_eq!(path_map.get("/b"), None);
+    }
+
+    #[test]
+    fn str_map_static_keys() {
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("if", 1), ("else", 2)]);
+        str_map.extend([(Cow::Borrowed("fn"), 3), (Cow::Owned("let".to_owned()), 4)]);
+
+        assert_eq!(str_map.get("if"), Some(&1));
+        assert_eq!(str_map.get("else"), Some(&2));
+        assert_eq!(str_map.get("fn"), Some(&3));
+        assert_eq!(str_map.get("let"), Some(&4));
+        assert_eq!(str_map.len(), 4);
+    }
+
+    #[test]
+    fn str_map_remove_and_retain() {
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("abc", 1), ("abd", 2), ("xyz", 3), ("x", 4)]);
+
+        assert_eq!(str_map.remove("abd"), Some(2));
+        assert_eq!(str_map.remove("abd"), None);
+        assert_eq!(str_map.get("abd"), None);
+        assert_eq!(str_map.len(), 3);
+
+        str_map.retain(|key, value| {
+            *value += 10;
+            key.len() == 3
+        });
+        assert_eq!(str_map.get("abc"), Some(&11));
+        assert_eq!(str_map.get("xyz"), Some(&13));
+        assert_eq!(str_map.get("x"), None);
+
+        str_map.insert("abd".to_owned(), 5);
+        assert_eq!(str_map.get("abd"), Some(&5));
+        assert_eq!(str_map.len(), 3);
+
+        // Only the shard which loses an entry is rebuilt.
+        str_map.extend([("w", 6), ("v", 7)]);
+        let rebuilds = |str_map: &PhStrMap<_>| {
+            str_map
+                .inner
+                .shards
+                .iter()
+                .map(|shard| shard.inner_map.rebuild_stats().rebuilds)
+                .collect::<Vec<_>>()
+        };
+        let before = rebuilds(&str_map);
+        str_map.retain(|key, _| key != "w");
+        assert_eq!(rebuilds(&str_map), [before[0] + 1, before[1]]);
+        assert_eq!(str_map.get("v"), Some(&7));
+        assert_eq!(str_map.get("w"), None);
+    }
+
+    #[test]
+    fn str_map_clone() {
+        let mut str_map = PhStrMap::with_options(MapOptions::new().verify_keys(true));
+        str_map.extend([("cat", 1), ("cow", 2), ("horse", 3)]);
+
+        let cloned = str_map.clone();
+        str_map.insert("cab".to_owned(), 4);
+        str_map["cat"] = 10;
+
+        assert_eq!(cloned.get("cat"), Some(&1));
+        assert_eq!(cloned.get("cab"), None);
+        assert_eq!(cloned.get("hat"), None);
+        assert_eq!(cloned.get("horse"), Some(&3));
+        assert_eq!(cloned.len(), 3);
+    }
+
+    #[test]
+    fn str_map_from_lines() {
+        let words = "apple\nbanana\r\n\ncherry\ndate";
+        let str_map = PhStrMap::from_lines(words.as_bytes(), str::len).unwrap();
+
+        assert_eq!(str_map.len(), 4);
+        assert_eq!(str_map.get("banana"), Some(&6));
+        assert_eq!(str_map.get("date"), Some(&4));
+        assert_eq!(str_map.get(""), None);
+    }
+
+    #[test]
+    fn bytes_map() {
+        let mut bytes_map = PhBytesMap::default();
+        bytes_map.extend([
+            (vec![0xde, 0xad, 0x00, 0x01], 1),
+            (vec![0xde, 0xad, 0xff, 0x01], 2),
+        ]);
+        bytes_map.extend([(&b"\x00"[..], 3)]);
+
+        assert_eq!(bytes_map.get(&[0xde, 0xad, 0xff, 0x01]), Some(&2));
+        assert_eq!(bytes_map.get(b"\x00"), Some(&3));
+        assert_eq!(bytes_map.get(b"\x01\x02"), None);
+        assert_eq!(bytes_map.len(), 3);
+        assert_eq!(
+            bytes_map.keys().collect::<Vec<_>>(),
+            [
+                &[0xde, 0xad, 0x00, 0x01][..],
+                &[0xde, 0xad, 0xff, 0x01],
+                &[0]
+            ]
+        );
+    }
+
+    #[test]
+    fn insert_replaces_existing_keys() {
+        let mut str_map = PhStrMap::with_options(MapOptions::new().duplicates(Duplicates::Error));
+        str_map.extend([("key-1", 1), ("key-2", 2)]);
+        assert_eq!(str_map.insert("key-1".to_owned(), 10), Some(1));
+        assert_eq!(str_map.insert("key-3".to_owned(), 3), None);
+        assert_eq!(str_map.get("key-1"), Some(&10));
+        assert_eq!(str_map.len(), 3);
+
+        let mut bytes_map =
+            PhBytesMap::with_options(MapOptions::new().duplicates(Duplicates::Error));
+        assert_eq!(bytes_map.insert(vec![1], 1), None);
+        assert_eq!(bytes_map.insert(vec![1], 2), Some(1));
+        assert_eq!(bytes_map.get(&[1]), Some(&2));
+
+        // Bulk construction still follows the policy.
+        assert!(matches!(
+            bytes_map.try_extend([(vec![1], 3)]),
+            Err(BuildError::DuplicateKeys { .. })
+        ));
+    }
+
+    #[test]
+    fn lookups_never_panic() {
+        use std::path::Path;
+
+        let mut map = PhMap::<&str, i32, str>::default();
+        assert_eq!(map.get("a"), None);
+        assert_eq!(map.get_mut("a"), None);
+        assert!(!map.contains_key("a"));
+        map.extend([("a", 1), ("bb", 2)]);
+        assert!(map.contains_key("bb"));
+        assert!(!map.contains_key(""));
+
+        let mut str_map = PhStrMap::default();
+        assert_eq!(str_map.get("a"), None);
+        assert!(!str_map.contains_key(""));
+        str_map.extend([("key-1", 1), ("key-2", 2)]);
+        for key in ["", "k", "key-", "key-3", "key-10", "\u{10ffff}"] {
+            assert_eq!(str_map.get(key), None);
+            assert_eq!(str_map.get_mut(key), None);
+            assert!(!str_map.contains_key(key));
+        }
+        assert_eq!(unsafe { *str_map.get_unchecked("key-2") }, 2);
+
+        let mut bytes_map = PhBytesMap::with_options(MapOptions::new().prefilter(10));
+        bytes_map.extend([(vec![0, 1], 1), (vec![0, 2], 2)]);
+        assert_eq!(bytes_map.get(&[]), None);
+        assert_eq!(bytes_map.get(&[0, 3]), None);
+        assert_eq!(bytes_map.get_mut(&[0, 1, 2]), None);
+        assert!(bytes_map.contains_key(&[0, 2]));
+
+        let mut path_map = PhPathMap::default();
+        path_map.extend([(Path::new("/a/b"), 1)]);
+        assert_eq!(path_map.get("/a/c"), None);
+        assert_eq!(path_map.get_mut("/a/b"), Some(&mut 1));
+        assert!(!path_map.contains_key("/"));
+    }
+
+    #[test]
+    fn bytes_map_single_key_shards() {
+        let long_key = vec![7; 1000];
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("user-100", 1), ("user-200", 2), ("admin", 4)]);
+        str_map.insert(String::from_utf8(long_key.clone()).unwrap(), 5);
+
+        assert_eq!(str_map.get("admin"), Some(&4));
+        assert_eq!(str_map.get("xxxxx"), None);
+        assert_eq!(str_map.get(&"\x07".repeat(1000)), Some(&5));
+        assert_eq!(str_map.get(&"\x08".repeat(1000)), None);
+        assert_eq!(str_map.get(&"\x07".repeat(999)), None);
+
+        let mut bytes_map = PhBytesMap::default();
+        bytes_map.insert(long_key, 5);
+        bytes_map.insert(vec![1, 2], 6);
+        assert_eq!(bytes_map.get(&[7; 1000]), Some(&5));
+        assert_eq!(bytes_map.get(&[7; 1001]), None);
+        assert_eq!(bytes_map.get(&[1, 2]), Some(&6));
+    }
+
+    #[test]
+    fn bytes_map_key_storage() {
+        // Each slot's key is only the selected bytes, and the full keys of a shard share one
+        // buffer, so keys don't allocate however long they are.
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(size_of::<InlineBytes>(), 32);
+
+        let keys = (0..100)
+            .map(|i| format!("{i:03}-a-key-which-is-too-long-to-store-inline").into_bytes())
+            .collect::<Vec<_>>();
+        let mut map = PhBytesMap::default();
+        map.extend(keys.iter().cloned().zip(0..));
+
+        let shard = &map.shards[0];
+        assert_eq!(shard.full_keys.len(), 100 * keys[0].len());
+        assert!(
+            shard
+                .inner_map
+                .keys()
+                .all(|k| matches!(k, InlineBytes::Inline { .. }))
+        );
+        assert!(keys.iter().zip(0..).all(|(k, i)| map.get(k) == Some(&i)));
+
+        assert_eq!(map.remove(&keys[11]), Some(11));
+        map.retain(|_, value| *value % 2 == 1);
+        map.insert(keys[0].clone(), 0);
+
+        // The last key took the place of the removed one.
+        let mut expected = keys.iter().zip(0..).collect::<Vec<_>>();
+        expected.swap_remove(11);
+        let expected = expected
+            .into_iter()
+            .filter(|(_, i)| *i % 2 == 1)
+            .chain([(&keys[0], 0)]);
+        assert!(
+            map.iter()
+                .map(|(k, v)| (k, *v))
+                .eq(expected.map(|(k, i)| (&k[..], i)))
+        );
+        assert_eq!(map.get(&keys[11]), None);
+    }
+
+    #[test]
+    fn inline_bytes_heap() {
+        let long = vec![1; INLINE_CAPACITY + 1];
+        assert!(matches!(
+            InlineBytes::from(&long[..INLINE_CAPACITY]),
+            InlineBytes::Inline { .. }
+        ));
+        assert!(matches!(InlineBytes::from(&long[..]), InlineBytes::Heap(_)));
+        assert_eq!(InlineBytes::from(long.clone()).as_ref(), &long[..]);
+
+        // Hashing whole keys which are too long to store inline puts them on the heap.
+        let mut map = PhBytesMap::with_options(MapOptions::new().compression(KeyCompression::Full));
+        map.extend([(long.clone(), 1), (vec![2; INLINE_CAPACITY + 1], 2)]);
+        assert!(
+            map.shards[0]
+                .inner_map
+                .keys()
+                .all(|k| matches!(k, InlineBytes::Heap(_)))
+        );
+        assert_eq!(map.get(&long), Some(&1));
+        assert_eq!(map.get(&[2; INLINE_CAPACITY + 1]), Some(&2));
+        assert_eq!(map.get(&[3; INLINE_CAPACITY + 1]), None);
+    }
+
+    #[test]
+    fn str_map_positions() {
+        let keys = (0..50)
+            .map(|i| format!("user-{:04}-region-{}", i * 37 % 1000, i % 7))
+            .collect::<Vec<_>>();
+
+        let mut str_map =
+            PhStrMap::with_options(MapOptions::new().compression(KeyCompression::Positions));
+        str_map.extend(keys.iter().cloned().zip(0..));
+
+        assert!(matches!(
+            str_map.inner.shards[0].selection,
+            Selection::Positions(_)
+        ));
+        assert!(
+            keys.iter()
+                .zip(0..)
+                .all(|(k, i)| str_map.get(k) == Some(&i))
+        );
+
+        str_map.insert("user-9999-region-9".to_owned(), 50);
+        assert_eq!(str_map.get("user-9999-region-9"), Some(&50));
+        assert_eq!(str_map.get(&keys[3]), Some(&3));
+        assert_eq!(str_map.remove(&keys[4]), Some(4));
+        assert_eq!(str_map.len(), 50);
+    }
+
+    #[test]
+    fn str_map_duplicate_keys() {
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("a", 1), ("bc", 2)]);
+
+        assert_eq!(
+            str_map.try_extend([
+                ("bc".to_owned(), 3),
+                ("d".to_owned(), 4),
+                ("d".to_owned(), 5)
+            ]),
+            Err(BuildError::DuplicateKeys {
+                keys: vec![b"d".to_vec(), b"bc".to_vec()]
+            })
+        );
+        assert_eq!(str_map.len(), 2);
+        assert_eq!(str_map.get("bc"), Some(&2));
+
+        str_map.try_extend([("d".to_owned(), 4)]).unwrap();
+        assert_eq!(str_map.get("d"), Some(&4));
+    }
+
+    #[test]
+    fn set() {
+        let set = |range: std::ops::Range<u32>| {
+            range.map(|i| i.to_string()).collect::<PhSet<String, str>>()
+        };
+        let mut a = set(0..10);
+        let b = set(5..15);
+
+        assert!(a.contains("3"));
+        assert!(!a.contains("10"));
+        assert!(!a.insert("3".to_owned()));
+        assert!(a.insert("20".to_owned()));
+        a.extend(["21".to_owned(), "21".to_owned(), "0".to_owned()]);
+        assert_eq!(a.len(), 12);
+
+        assert_eq!(
+            a.intersection(&b).collect::<Vec<_>>(),
+            ["5", "6", "7", "8", "9"]
+        );
+        assert_eq!(a.difference(&b).count(), 7);
+        assert_eq!(a.union(&b).count(), 17);
+        assert_eq!(a.symmetric_difference(&b).count(), 12);
+
+        let c = a.intersection(&b).cloned().collect::<PhSet<String, str>>();
+        assert!(c.is_subset(&a) && c.is_subset(&b) && a.is_superset(&c));
+        assert!(!a.is_disjoint(&b));
+        assert!(
+            a.difference(&b)
+                .cloned()
+                .collect::<PhSet<String, str>>()
+                .is_disjoint(&b)
+        );
+    }
+
+    #[test]
+    fn str_set() {
+        let mut keywords = PhStrSet::default();
+        keywords.extend(["if", "else", "while", "if"]);
+
+        assert!(keywords.insert("loop".to_owned()));
+        assert!(!keywords.insert("else".to_owned()));
+        assert_eq!(keywords.len(), 4);
+        assert!(keywords.contains("while"));
+        assert!(!keywords.contains("whale"));
+        assert!(!keywords.contains("for"));
+        assert_eq!(
+            keywords.iter().collect::<Vec<_>>(),
+            ["if", "else", "while", "loop"]
+        );
+        assert_eq!(format!("{:?}", PhStrSet::default()), "{}");
+    }
+
+    #[test]
+    fn str_map_from_iter() {
+        let str_map: PhStrMap<u32> = (0..100).map(|i| (i.to_string(), i)).collect();
+
+        assert_eq!(str_map.len(), 100);
+        assert!((0..100).all(|i| str_map.get(&i.to_string()) == Some(&i)));
+        assert!(
+            str_map
+                .inner
+                .shards
+                .iter()
+                .all(|shard| shard.inner_map.rebuild_stats().rebuilds == 1)
+        );
+
+        let str_map: PhStrMap<u32> = [("a", 1), ("b", 2)].into_iter().collect();
+        assert_eq!(str_map.get("b"), Some(&2));
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn str_map_normalization() {
+        use crate::NormalizationForm;
+
+        let mut str_map =
+            PhStrMap::with_options(MapOptions::new().normalization(NormalizationForm::Nfc));
+        str_map.insert("caf\u{e9}".to_owned(), 1);
+
+        assert_eq!(str_map.get("caf\u{e9}"), Some(&1));
+        assert_eq!(str_map.get("cafe\u{301}"), Some(&1));
+        assert_eq!(str_map.keys().collect::<Vec<_>>(), ["caf\u{e9}"]);
+    }
+
+    #[test]
+    fn str_map_compression_stats() {
+        let mut str_map = PhStrMap::default();
+        str_map.extend([
+            ("user-100", 1),
+            ("user-200", 2),
+            ("user-300", 3),
+            ("admin", 4),
+        ]);
+
+        let stats = str_map.compression_stats();
+        assert_eq!(stats.keys, 4);
+        assert_eq!(stats.shards, 2);
+        assert_eq!(stats.key_bytes, 3 * 8 + 5);
+        assert_eq!(stats.hashed_bytes, 3 + 5);
+        assert!(stats.slots >= 4);
+        assert!(stats.occupancy() <= 1.);
+    }
+
+    #[test]
+    fn path_map() {
+        use std::path::{Path, PathBuf};
+
+        let mut path_map = PhPathMap::default();
+        path_map.extend([
+            (Path::new("/usr/bin/env"), 1),
+            (Path::new("/usr/lib/env"), 2),
+        ]);
+        path_map.insert(PathBuf::from("/etc/hosts"), 3);
+
+        assert_eq!(path_map.get("/usr/bin/env"), Some(&1));
+        assert_eq!(path_map[Path::new("/usr/lib/env")], 2);
+        assert_eq!(path_map.get(&PathBuf::from("/etc/hosts")), Some(&3));
+        assert_eq!(path_map.get("/usr/bin/sh"), None);
+        assert_eq!(
+            path_map.keys().collect::<Vec<_>>(),
+            ["/usr/bin/env", "/usr/lib/env", "/etc/hosts"].map(Path::new)
+        );
+
+        assert_eq!(path_map.remove("/usr/lib/env"), Some(2));
+        assert_eq!(path_map.len(), 2);
+    }
+
+    #[test]
+    fn str_interner() {
+        let mut interner = PhStrInterner::default();
+        interner.extend(["fn", "let", "fn", "mut"]);
+
+        let id = interner.intern("match");
+        assert_eq!(interner.intern("let"), interner.get("let").unwrap());
+        assert_eq!(interner.intern("match"), id);
+        assert_eq!(interner.get("if"), None);
+
+        // "let" and "mut" are told apart by their first byte, so interning "lat" re-selects
+        // every key of their length.
+        interner.extend(["lat".to_owned()]);
+        assert_eq!(interner.len(), 5);
+        assert_eq!(
+            interner.iter().map(|(_, key)| key).collect::<Vec<_>>(),
+            ["fn", "let", "mut", "match", "lat"]
+        );
+        for (id, key) in interner.iter() {
+            assert_eq!(interner.get(key), Some(id));
+            assert_eq!(interner.resolve(id), key);
+        }
+    }
+
+    #[test]
+    fn prefix_map() {
+        let prefix_map = [
+            ("/", 0),
+            ("/api", 1),
+            ("/api/v1", 2),
+            ("/static", 3),
+            ("/api/v2", 4),
+        ]
+        .into_iter()
+        .collect::<PhPrefixMap<_>>();
+
+        assert_eq!(
+            prefix_map.longest_prefix("/api/v1/users"),
+            Some(("/api/v1", &2))
+        );
+        assert_eq!(prefix_map.longest_prefix("/api/v3"), Some(("/api", &1)));
+        assert_eq!(prefix_map.longest_prefix("/index.html"), Some(("/", &0)));
+        assert_eq!(prefix_map.longest_prefix("api"), None);
+        assert_eq!(
+            prefix_map.prefixes("/api/v2").collect::<Vec<_>>(),
+            [("/api/v2", &4), ("/api", &1), ("/", &0)]
+        );
+    }
+
+    #[test]
+    fn str_map_duplicates() {
+        let kvs = [("a", 1), ("bb", 2), ("a", 3), ("c", 4), ("bb", 5)];
+
+        let mut keep_first =
+            PhStrMap::with_options(MapOptions::new().duplicates(Duplicates::KeepFirst));
+        keep_first.extend(kvs);
+        keep_first.extend([("c", 6), ("d", 7)]);
+        assert_eq!(
+            keep_first.iter().collect::<Vec<_>>(),
+            [("a", &1), ("c", &4), ("d", &7), ("bb", &2)]
+        );
+
+        let mut keep_last =
+            PhStrMap::with_options(MapOptions::new().duplicates(Duplicates::KeepLast));
+        keep_last.extend(kvs);
+        keep_last.insert("c".to_owned(), 6);
+        assert_eq!(
+            keep_last.iter().collect::<Vec<_>>(),
+            [("a", &3), ("c", &6), ("bb", &5)]
+        );
+
+        let mut error = PhStrMap::with_options(MapOptions::new().duplicates(Duplicates::Error));
+        assert!(
+            error
+                .try_extend([("a".to_owned(), 1), ("a".to_owned(), 2)])
+                .is_err()
+        );
+        assert!(error.is_empty());
+    }
+
+    #[test]
+    fn str_map_get_batch() {
+        let mut str_map = PhStrMap::default();
+        str_map.extend((0..40).map(|i| (format!("key-{i}"), i)));
+
+        let queries = (0..50).map(|i| format!("key-{i}")).collect::<Vec<_>>();
+        let expected = queries.iter().map(|k| str_map.get(k)).collect::<Vec<_>>();
+
+        assert_eq!(str_map.get_batch(&queries), expected);
+        assert_eq!(
+            str_map.get_batch(&["key-3", "", "key-39"]),
+            [Some(&3), None, Some(&39)]
+        );
+    }
+
+    #[test]
+    fn str_map_prefilter() {
+        let mut str_map = PhStrMap::with_options(MapOptions::new().prefilter(0));
+        str_map.extend([
+            ("if".to_owned(), 1),
+            ("fn".to_owned(), 2),
+            ("for".to_owned(), 3),
+        ]);
+
+        assert_eq!(str_map.get("if"), Some(&1));
+        assert_eq!(str_map.get("fn"), Some(&2));
+        assert_eq!(str_map.get("for"), Some(&3));
+        assert_eq!(str_map.get("xy"), None);
+        assert_eq!(str_map.get("xor"), None);
+    }
+
+    #[test]
+    fn enum_map() {
+        #[derive(Debug, PartialEq)]
+        enum Color {
+            Red,
+            Green,
+            Blue,
+        }
+
+        impl PhKey for Color {
+            const COUNT: usize = 3;
+
+            fn index(&self) -> usize {
+                match self {
+                    Self::Red => 0,
+                    Self::Green => 1,
+                    Self::Blue => 2,
+                }
+            }
+
+            fn from_index(index: usize) -> Option<Self> {
+                [Self::Red, Self::Green, Self::Blue].into_iter().nth(index)
+            }
+        }
+
+        let map: PhEnumMap<Color, &str> = [(Color::Red, "red"), (Color::Blue, "blue")]
+            .into_iter()
+            .collect();
+
+        assert_eq!(map.get(&Color::Red), Some(&"red"));
+        assert_eq!(map.get(&Color::Green), None);
+        assert_eq!(Color::Blue.to_bytes(), [2, 0, 0, 0]);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(Color::Red, &"red"), (Color::Blue, &"blue")]
+        );
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_ph_key() {
+        #[derive(Debug, PartialEq, PhKey)]
+        enum Direction {
+            North,
+            East,
+            South,
+            West,
+        }
+
+        assert_eq!(Direction::COUNT, 4);
+        assert_eq!(Direction::South.index(), 2);
+        assert_eq!(Direction::from_index(3), Some(Direction::West));
+        assert_eq!(Direction::from_index(4), None);
+        assert_eq!(Direction::East.to_bytes(), [1, 0, 0, 0]);
+
+        let mut map = PhEnumMap::default();
+        map.insert(Direction::North, "up");
+        map.insert(Direction::West, "left");
+
+        assert_eq!(map.get(&Direction::West), Some(&"left"));
+        assert_eq!(map.get(&Direction::East), None);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(Direction::North, &"up"), (Direction::West, &"left")]
+        );
+    }
+
+    #[test]
+    fn bytes_key() {
+        struct Route {
+            from: String,
+            to: String,
+            hops: u16,
+        }
+
+        impl PhBytesKey for Route {
+            fn encode(&self, out: &mut Vec<u8>) {
+                self.from.encode(out);
+                self.to.encode(out);
+                self.hops.encode(out);
+            }
+        }
+
+        let route = |from: &str, to: &str, hops| Route {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            hops,
+        };
+
+        assert_ne!(
+            route("ab", "c", 1).to_bytes(),
+            route("a", "bc", 1).to_bytes()
+        );
+
+        let mut map = PhMap::<Vec<u8>, u32, [u8]>::default();
+        map.extend([
+            (route("ab", "c", 1).to_bytes(), 1),
+            (route("a", "bc", 1).to_bytes(), 2),
+        ]);
+
+        assert_eq!(map.get(&route("a", "bc", 1).to_bytes()), Some(&2));
+        assert_eq!(map.get(&route("a", "bc", 2).to_bytes()), None);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_ph_bytes_key() {
+        #[derive(PhBytesKey)]
+        struct Route {
+            from: String,
+            to: String,
+            hops: u16,
+        }
+
+        #[derive(PhBytesKey)]
+        struct Point(i32, i32);
+
+        let route = |from: &str, to: &str, hops| Route {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            hops,
+        };
+
+        let mut expected = vec![];
+        "ab".encode(&mut expected);
+        "c".encode(&mut expected);
+        1u16.encode(&mut expected);
+        assert_eq!(route("ab", "c", 1).to_bytes(), expected);
+        assert_ne!(
+            route("ab", "c", 1).to_bytes(),
+            route("a", "bc", 1).to_bytes()
+        );
+        assert_eq!(Point(1, -1).to_bytes(), [1, 0, 0, 0, 255, 255, 255, 255]);
+
+        let mut map = PhMap::<Vec<u8>, u32, [u8]>::default();
+        map.extend([
+            (route("ab", "c", 1).to_bytes(), 1),
+            (route("a", "bc", 1).to_bytes(), 2),
+        ]);
+
+        assert_eq!(map.get(&route("a", "bc", 1).to_bytes()), Some(&2));
+        assert_eq!(map.get(&route("a", "bc", 2).to_bytes()), None);
+    }
+
+    #[cfg(feature = "portable-hash")]
+    #[test]
+    fn portable_hash() {
+        use ph::BuildSeededHasher as _;
+
+        let hasher = crate::BuildPortableHasher;
+
+        assert_eq!(hasher.hash_one(&7usize, 3), hasher.hash_one(&7u64, 3));
+
+        let mut le_bytes = hasher.build_hasher(3);
+        le_bytes.write(&[2, 1]);
+        assert_eq!(hasher.hash_one(&0x0102u16, 3), le_bytes.finish());
+    }
+
+    #[test]
+    fn profiles() {
+        for profile in [
+            Profile::Balanced,
+            Profile::MinMemory,
+            Profile::MaxLookupSpeed,
+            Profile::FastBuild,
+        ] {
+            let mut map = PhMap::<String, u32, str>::with_profile(profile);
+            map.extend((0..100).map(|i| (i.to_string(), i * 2)));
+
+            assert!((0..100).all(|i| map.get(&i.to_string()) == Some(&(i * 2))));
+        }
+
+        assert_eq!(Profile::Balanced.fingerprint_bits(), 64);
+        assert!(!Profile::Balanced.is_minimal());
+        assert_eq!(Profile::MinMemory.fingerprint_bits(), 16);
+        assert!(Profile::MinMemory.is_minimal());
+        assert_eq!(
+            PhMapBuilder::new()
+                .profile(Profile::MinMemory)
+                .bits_per_seed(10)
+                .to_profile()
+                .fingerprint_bits(),
+            16
+        );
+
+        let balanced = (0..100)
+            .map(|i| (i.to_string(), i))
+            .collect::<PhMap<String, u32, str>>();
+        let mut minimal = PhMap::<String, u32, str>::with_profile(Profile::MinMemory);
+        minimal.extend((0..100).map(|i| (i.to_string(), i)));
+
+        assert_eq!(minimal.slot_count(), minimal.len());
+        assert!(minimal.memory_usage().hashes < balanced.memory_usage().hashes);
+        assert_eq!(minimal.verify(), Ok(()));
+        assert!((0..100).all(|i| minimal.get(&i.to_string()) == Some(&i)));
+        assert!(
+            minimal
+                .iter()
+                .map(|(k, v)| (k.clone(), *v))
+                .eq(balanced.iter().map(|(k, v)| (k.clone(), *v)))
+        );
+
+        assert_eq!(minimal.remove("5"), Some(5));
+        minimal.extend([("100".to_owned(), 100)]);
+        assert_eq!(minimal.slot_count(), minimal.len());
+        assert_eq!(minimal.get("5"), None);
+        assert!(
+            (0..=100)
+                .filter(|&i| i != 5)
+                .all(|i| minimal.get(&i.to_string()) == Some(&i))
+        );
+
+        let frozen = minimal.freeze();
+        assert_eq!(frozen.get("100"), Some(&100));
+        assert_eq!(frozen.get("5"), None);
+
+        let mut set = PhSet::<String, str>::with_profile(Profile::MinMemory);
+        set.try_extend((0..100).map(|i| i.to_string())).unwrap();
+        assert!((0..100).all(|i| set.contains(&i.to_string())));
+    }
+
+    #[test]
+    fn builder() {
+        let builder = PhMapBuilder::new()
+            .profile(Profile::MinMemory)
+            .bits_per_seed(10);
+        let profile = builder.to_profile();
+        assert_eq!(
+            profile,
+            Profile::Custom {
+                bits_per_seed: Some(10),
+                bucket_size100: None,
+                base: &Profile::MinMemory,
+            }
+        );
+        // The bucket size is still the one chosen by the profile.
+        assert_eq!(profile.params(1000).seed_size.0, 10);
+        assert_eq!(
+            profile.params(1000).bucket_size100,
+            Profile::MinMemory.params(1000).bucket_size100
+        );
+
+        // Starting from a custom profile keeps its parameters and its base.
+        assert_eq!(
+            PhMapBuilder::new()
+                .profile(profile)
+                .bucket_size100(300)
+                .to_profile(),
+            Profile::Custom {
+                bits_per_seed: Some(10),
+                bucket_size100: Some(300),
+                base: &Profile::MinMemory,
+            }
+        );
+
+        let mut map: PhMap<String, u32, str> = builder
+            .bucket_size100(300)
+            .verify_slots(true)
+            .build((0..100).map(|i| (i.to_string(), i)));
+        map.extend([("100".to_owned(), 100)]);
+        assert!((0..=100).all(|i| map.get(&i.to_string()) == Some(&i)));
+
+        assert_eq!(
+            PhMapBuilder::new().profile(Profile::FastBuild).to_profile(),
+            Profile::FastBuild
+        );
+    }
+
+    #[test]
+    fn builder_hasher() {
+        #[derive(Default)]
+        struct SeededSipHasher;
+
+        impl BuildSeededHasher for SeededSipHasher {
+            type Hasher = std::hash::DefaultHasher;
+
+            fn build_hasher(&self, seed: u32) -> Self::Hasher {
+                let mut hasher = std::hash::DefaultHasher::new();
+                hasher.write_u32(seed);
+                hasher
+            }
+        }
+
+        let mut map: PhMap<String, u32, str, SeededSipHasher> = PhMapBuilder::new()
+            .hasher::<SeededSipHasher>()
+            .try_build((0..100).map(|i| (i.to_string(), i)))
+            .unwrap();
+        assert_eq!(
+            PhMap::<String, u32, str, SeededSipHasher>::hash_key("5"),
+            SeededSipHasher.hash_one("5", 0)
+        );
+
+        assert_eq!(map.insert("100".to_owned(), 100), None);
+        assert_eq!(map.insert("5".to_owned(), 50), Some(5));
+        assert_eq!(map.remove("6"), Some(6));
+        assert!(map.get("6").is_none());
+        assert_eq!(map.get("5"), Some(&50));
+        assert_eq!(map.iter().count(), 100);
+    }
+
+    #[test]
+    fn compressed_map() {
+        let mut map = PhCompressedMap::<&str, _, str>::new(RunLength);
+        map.insert("zeros", [0; 1000]);
+        map.insert("mixed", b"aaabccccd");
+        map.insert("empty", b"");
+
+        assert!(map.get_compressed("zeros").unwrap().len() < 10);
+        assert_eq!(map.get_decompressed("zeros"), Some(Ok(vec![0; 1000])));
+        assert_eq!(map.get_decompressed("empty"), Some(Ok(vec![])));
+        assert_eq!(map.get_decompressed("missing"), None);
+
+        map.insert("empty", b"bbb");
+        assert_eq!(map.get_decompressed("empty"), Some(Ok(b"bbb".to_vec())));
+        assert_eq!(map.get_decompressed("zeros"), Some(Ok(vec![0; 1000])));
+
+        let mut output = b"stale".to_vec();
+        assert_eq!(map.get_into("mixed", &mut output), Some(Ok(())));
+        assert_eq!(output, b"aaabccccd");
+
+        let mut output = vec![];
+        assert_eq!(
+            RunLength.decompress(&[3, b'a', 2], &mut output),
+            Err(RunLengthError)
+        );
+        assert_eq!(
+            RunLength.decompress(&[0, b'a'], &mut output),
+            Err(RunLengthError)
+        );
+    }
+
+    #[test]
+    fn perfect_map() {
+        fn check<M>(map: &M, expected: &phf::Map<&'static str, &'static str>)
+        where
+            M: ?Sized + PerfectMap<str, Value = &'static str>,
+        {
+            assert_eq!(map.len(), expected.len());
+            assert!(!map.is_empty());
+            for (key, value) in expected.entries() {
+                assert_eq!(map.get(key), Some(value));
+                assert!(map.contains_key(key));
+            }
+            assert_eq!(map.get("not-a-test-key"), None);
+            assert!(!map.contains_key(""));
+
+            let mut values = map.values().collect::<Vec<_>>();
+            let mut expected_values = expected.values().collect::<Vec<_>>();
+            values.sort();
+            expected_values.sort();
+            assert_eq!(values, expected_values);
+        }
+
+        let phf_map = &codegen::TEST_PHF_MAP;
+        check(phf_map, phf_map);
+
+        let map: PhMap<&str, &str, str> = phf_map.entries().map(|(k, v)| (*k, *v)).collect();
+        check(&map, phf_map);
+
+        let mut str_map = PhStrMap::default();
+        str_map.extend(phf_map.entries().map(|(k, v)| (*k, *v)));
+        check(&str_map, phf_map);
+
+        let mut bytes_map = PhBytesMap::default();
+        bytes_map.extend(phf_map.entries().map(|(k, v)| (k.as_bytes(), *v)));
+        assert_eq!(PerfectMap::len(&bytes_map), phf_map.len());
+        assert!(
+            phf_map
+                .entries()
+                .all(|(k, v)| PerfectMap::get(&bytes_map, k.as_bytes()) == Some(v))
+        );
+    }
+
+    #[test]
+    fn get_copied_and_cloned() {
+        let map: PhMap<&str, u32, str> = PhMap::from([("a", 1), ("b", 2)]);
+        assert_eq!(map.get_copied("a"), Some(1));
+        assert_eq!(map.get_copied("c"), None);
+
+        let map: PhMap<&str, String, str> = PhMap::from([("a", "x".to_owned())]);
+        assert_eq!(map.get_cloned("a"), Some("x".to_owned()));
+        assert_eq!(map.get_cloned("b"), None);
+
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("user-1", vec![1]), ("user-2", vec![2])]);
+        assert_eq!(str_map.get_cloned("user-2"), Some(vec![2]));
+        assert_eq!(str_map.get_cloned("user-3"), None);
+
+        let mut bytes_map = PhBytesMap::default();
+        bytes_map.extend([(&b"ab"[..], 1u8), (&b"cd"[..], 2)]);
+        assert_eq!(bytes_map.get_copied(b"cd"), Some(2));
+        assert_eq!(bytes_map.get_cloned(b"ab"), Some(1));
+        assert_eq!(bytes_map.get_copied(b"ef"), None);
+    }
+
+    #[test]
+    fn from_values_with_key() {
+        #[derive(Debug, PartialEq)]
+        struct User {
+            name: String,
+            id: u32,
+        }
+
+        let users = ["alice", "bob", "carol"]
+            .into_iter()
+            .enumerate()
+            .map(|(id, name)| User {
+                name: name.to_owned(),
+                id: id as u32,
+            });
+        let map: PhMap<String, User, str> =
+            PhMap::from_values_with_key(users, |user| user.name.clone());
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get("bob").map(|user| user.id), Some(1));
+        assert_eq!(map.get("dave"), None);
+        assert_eq!(
+            map.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(),
+            ["alice", "bob", "carol"]
+        );
+        assert!(map.iter().all(|(key, user)| *key == user.name));
+    }
+
+    #[test]
+    fn dump_layout() {
+        let mut map: PhMap<&str, u32, str> = PhMap::from([("a", 1), ("b", 2), ("c", 3)]);
+        map.remove("b");
+
+        let mut out = vec![];
+        map.dump_layout(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+
+        assert!(lines[0].contains("slot") && lines[0].contains("occupied"));
+        assert_eq!(lines.len(), map.slot_count() + 2);
+        assert_eq!(
+            lines[1..=map.slot_count()]
+                .iter()
+                .filter(|line| line.contains("true"))
+                .count(),
+            2
+        );
+        assert!(!out.contains("\"a\""));
+        let holes = map.slot_count() - 2;
+        assert!(lines.last().unwrap().starts_with(&format!(
+            "slots: {}, occupied: 2, holes: {holes}",
+            map.slot_count()
+        )));
+
+        let mut out = vec![];
+        map.dump_layout_with_keys(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("\"a\"") && out.contains("\"c\"") && !out.contains("\"b\""));
+    }
+
+    #[test]
+    fn leak() {
+        static MAP: std::sync::OnceLock<&PhMap<&str, u32, str>> = std::sync::OnceLock::new();
+        let map = MAP.get_or_init(|| PhMap::from([("a", 1), ("b", 2)]).leak());
+        assert_eq!(map.get("b"), Some(&2));
+
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("user-1", 1)]);
+        let str_map: &'static PhStrMap<u32> = str_map.leak();
+        assert_eq!(str_map.get("user-1"), Some(&1));
+
+        let mut bytes_map = PhBytesMap::default();
+        bytes_map.extend([(&b"ab"[..], 1)]);
+        let bytes_map: &'static PhBytesMap<u32> = bytes_map.leak();
+        assert_eq!(bytes_map.get(b"ab"), Some(&1));
+    }
+
+    #[test]
+    fn estimate_memory() {
+        type Map = PhMap<String, u64, str>;
+
+        let empty = Map::estimate_memory(0, 0);
+        assert_eq!(empty.slots, 0);
+        assert_eq!(empty.values, 0);
+
+        let estimate = Map::estimate_memory(1000, 8);
+        assert!(estimate.slots >= 1000);
+        assert_eq!(estimate.hashes, estimate.slots * size_of::<u64>());
+        assert_eq!(estimate.values, estimate.slots * size_of::<Option<u64>>());
+        assert_eq!(
+            estimate.keys,
+            1000 * (size_of::<String>() + 8) + estimate.slots * size_of::<u32>()
+        );
+        assert!(Map::estimate_memory(1000, 16).total() > estimate.total());
+        assert!(Map::estimate_memory(2000, 8).total() > estimate.total());
+
+        let map: Map = (0..1000u64).map(|i| (format!("key-{i:04}"), i)).collect();
+        let usage = map.memory_usage();
+        assert!(usage.values >= estimate.values / 2 && usage.values <= estimate.values * 2);
+        assert!(usage.keys >= estimate.keys / 2 && usage.keys <= estimate.keys * 2);
+    }
+
+    #[test]
+    fn get_unchecked_with_index() {
+        let mut map: PhMap<&str, u32, str> = PhMap::from([("a", 1), ("b", 2), ("c", 3)]);
+
+        for (key, value) in [("a", 1), ("b", 2), ("c", 3)] {
+            let (idx, found) = unsafe { map.get_unchecked_with_index(key) };
+            assert_eq!(*found, value);
+            assert_eq!(Some(idx), map.index_of(key));
+            assert_eq!(map.get_by_index(idx), Some((&key, &value)));
+        }
+
+        let (idx, value) = unsafe { map.get_unchecked_mut_with_index("b") };
+        *value = 20;
+        assert_eq!(map.get_by_index(idx), Some((&"b", &20)));
+    }
+
+    #[test]
+    fn bytes_map_remove_missing_key() {
+        let mut str_map = PhStrMap::default();
+        str_map.extend([("cat", 1), ("cog", 2)]);
+        // Only the middle byte distinguishes the keys, so "hat" looks up the slot of "cat".
+        assert_eq!(str_map.get("hat"), Some(&1));
+
+        assert_eq!(str_map.remove("hat"), None);
+        assert_eq!(str_map.get("cat"), Some(&1));
+        assert_eq!(str_map.len(), 2);
+
+        assert_eq!(str_map.remove("cat"), Some(1));
+        assert_eq!(str_map.get("cog"), Some(&2));
+        assert_eq!(str_map.iter().collect::<Vec<_>>(), [("cog", &2)]);
     }
 
     #[test]
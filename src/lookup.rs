@@ -0,0 +1,72 @@
+use std::hash::Hash;
+
+use ph::BuildSeededHasher;
+
+use crate::PhMap;
+
+/// The outcome of [`PhMap::get_detailed`], describing why a lookup did or didn't succeed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupResult<'a, KOwned, V> {
+    /// The perfect hash function did not map the key to any slot in the map.
+    NoSlot,
+    /// The key mapped to `slot`, but the hash stored in that slot didn't match. `stored_key`
+    /// is the key which actually occupies the slot, if any.
+    FingerprintMismatch {
+        slot: usize,
+        stored_key: Option<&'a KOwned>,
+    },
+    /// The stored hash matched. `stored_key` is the key that was inserted into this slot,
+    /// which will differ from the queried key if their hashes collide.
+    Found {
+        slot: usize,
+        stored_key: &'a KOwned,
+        value: &'a V,
+    },
+}
+
+impl<'a, KOwned, V> LookupResult<'a, KOwned, V> {
+    pub fn value(&self) -> Option<&'a V> {
+        match self {
+            Self::Found { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<KOwned, V, KRef, H> PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    /// Like [`PhMap::get`], but reports why the lookup failed. This is intended for debugging.
+    pub fn get_detailed<K>(&self, key: &K) -> LookupResult<'_, KOwned, V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let hash = self.to_index.hasher().hash_one(key.as_ref(), 0);
+        let Some(slot) = self
+            .to_index
+            .get_with_top_level_hash(key.as_ref(), hash)
+            .filter(|idx| *idx < self.top_level_hashes.len())
+        else {
+            return LookupResult::NoSlot;
+        };
+
+        let stored_key = self
+            .occupied
+            .contains(slot)
+            .then(|| &self.keys[self.key_indices[slot] as usize]);
+
+        match (stored_key, &self.values[slot]) {
+            (Some(stored_key), Some(value)) if self.top_level_hashes.matches(slot, hash) => {
+                LookupResult::Found {
+                    slot,
+                    stored_key,
+                    value,
+                }
+            }
+            _ => LookupResult::FingerprintMismatch { slot, stored_key },
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::hash::Hash;
+
+use ph::BuildSeededHasher;
+
+use crate::{PhBytesMap, PhMap, debug::bits_per_key, seed_bits};
+
+/// Predicted memory usage of a [`PhMap`], created by [`PhMap::estimate_memory`]. All sizes
+/// are in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryEstimate {
+    /// Expected number of value slots, including holes left by the perfect hash function.
+    pub slots: usize,
+    /// Size of the perfect hash function's seeds.
+    pub function: usize,
+    /// Size of the per-slot hashes used to reject keys which aren't in the map.
+    pub hashes: usize,
+    /// Size of the per-slot value storage.
+    pub values: usize,
+    /// Size of the owned keys, including `avg_key_bytes` of heap storage per key, and the
+    /// per-slot index of each slot's key.
+    pub keys: usize,
+}
+
+impl MemoryEstimate {
+    pub fn total(&self) -> usize {
+        self.function + self.hashes + self.values + self.keys
+    }
+}
+
+/// Memory used by a [`PhMap`], returned by [`PhMap::memory_usage`]. All sizes are in bytes and
+/// include spare capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Size of the perfect hash function, including its heap allocations.
+    pub function: usize,
+    /// Size of the per-slot hashes used to reject keys which aren't in the map.
+    pub hashes: usize,
+    /// Size of the per-slot value storage and occupancy bitmap.
+    pub values: usize,
+    /// Size of the owned keys and the per-slot index of each slot's key, plus the size of the
+    /// data each key refers to outside of itself, such as the heap storage of a `String` or
+    /// `Vec<u8>`. Keys which store their data inline are only counted once.
+    pub keys: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.function + self.hashes + self.values + self.keys
+    }
+}
+
+/// How much of each key a [`PhBytesMap`] or [`PhStrMap`](crate::PhStrMap) actually hashes,
+/// returned by [`PhBytesMap::compression_stats`]. Sizes are in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompressionStats {
+    pub keys: usize,
+    pub shards: usize,
+    /// Total length of the keys.
+    pub key_bytes: usize,
+    /// Total length of the bytes selected from each key to be hashed and compared.
+    pub hashed_bytes: usize,
+    /// Size of the perfect hash functions of all shards.
+    pub function_bytes: usize,
+    /// Number of value slots, including holes left by the perfect hash functions.
+    pub slots: usize,
+}
+
+impl CompressionStats {
+    /// Size of the perfect hash functions in bits per key.
+    pub fn bits_per_key(&self) -> f64 {
+        bits_per_key(self.function_bytes, self.keys)
+    }
+
+    /// Fraction of the key bytes which are hashed, so lower is better.
+    pub fn hashed_fraction(&self) -> f64 {
+        if self.key_bytes == 0 {
+            0.
+        } else {
+            self.hashed_bytes as f64 / self.key_bytes as f64
+        }
+    }
+
+    /// Fraction of the slots which hold a value.
+    pub fn occupancy(&self) -> f64 {
+        if self.slots == 0 {
+            0.
+        } else {
+            self.keys as f64 / self.slots as f64
+        }
+    }
+}
+
+impl<V> PhBytesMap<V> {
+    pub fn compression_stats(&self) -> CompressionStats {
+        let mut stats = CompressionStats {
+            shards: self.shards.len(),
+            ..Default::default()
+        };
+
+        for shard in &self.shards {
+            let inner = &shard.inner_map;
+
+            stats.keys += inner.len();
+            stats.key_bytes += shard.full_keys.len();
+            stats.hashed_bytes += inner.len() * shard.selection.selected_len();
+            stats.function_bytes += inner.function_bytes();
+            stats.slots += inner.values.len();
+        }
+
+        stats
+    }
+}
+
+/// The size of the data `key` refers to outside of itself, such as the heap storage of a
+/// `String`. Keys which store their bytes inline, like short
+/// [`InlineBytes`](crate::inline_bytes::InlineBytes), are already counted by
+/// `size_of::<KOwned>()`, so they add nothing.
+fn out_of_line_bytes<KOwned, KRef>(key: &KOwned) -> usize
+where
+    KRef: ?Sized,
+    KOwned: AsRef<KRef>,
+{
+    let data = key.as_ref();
+    let key_start = (key as *const KOwned).addr();
+    let data_start = (data as *const KRef).cast::<u8>().addr();
+
+    if (key_start..key_start + size_of::<KOwned>()).contains(&data_start) {
+        0
+    } else {
+        size_of_val(data)
+    }
+}
+
+/// PHast's output range is slightly larger than the number of keys. This is an estimate, the
+/// actual number of holes depends on the key set.
+const SLOT_OVERHEAD_PERCENT: usize = 1;
+
+impl<KOwned, V, KRef, H> PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    /// Number of value slots, including holes left by the perfect hash function.
+    pub fn slot_count(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Measures the memory used by this map, for comparison with other maps or with
+    /// [`PhMap::estimate_memory`].
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            function: self.function_bytes(),
+            hashes: self.top_level_hashes.size_bytes(),
+            values: self.values.capacity() * size_of::<Option<V>>() + self.occupied.size_bytes(),
+            keys: self.keys.capacity() * size_of::<KOwned>()
+                + self.key_indices.capacity() * size_of::<u32>()
+                + self.keys.iter().map(out_of_line_bytes).sum::<usize>(),
+        }
+    }
+
+    /// Predicts the memory used by a map of `num_keys` keys, each owning `avg_key_bytes` bytes
+    /// of heap storage (e.g. the length of a `String` key), without building it.
+    pub fn estimate_memory(num_keys: usize, avg_key_bytes: usize) -> MemoryEstimate {
+        let slots = num_keys + (num_keys * SLOT_OVERHEAD_PERCENT).div_ceil(100);
+
+        let bits = seed_bits(num_keys);
+        let keys_per_bucket = ph::phast::bits_per_seed_to_100_bucket_size(bits) as usize;
+        let buckets = (num_keys * 100).div_ceil(keys_per_bucket.max(1));
+        let function = (buckets * bits as usize).div_ceil(8);
+
+        MemoryEstimate {
+            slots,
+            function,
+            hashes: slots * size_of::<u64>(),
+            values: slots * size_of::<Option<V>>(),
+            keys: num_keys * (size_of::<KOwned>() + avg_key_bytes) + slots * size_of::<u32>(),
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    hash::Hash,
+    marker::PhantomData,
+    ops::Range,
+};
+
+use ph::BuildSeededHasher;
+
+use crate::{Function, KeyHasher, PhMap, Profile, build_function};
+
+/// The inner table for a single outer key of a [`Ph2Map`].
+struct Group {
+    to_index: Function,
+    keys: Range<usize>,
+    slots: Range<usize>,
+}
+
+/// A two-level map, where the outer perfect hash function maps `K1` to a contiguous range of
+/// a single shared table keyed by `K2`. This is much more compact than nesting `PhMap`s, and
+/// a lookup is two probes into densely-packed storage.
+pub struct Ph2Map<K1, K2, V, K1Ref = K1, K2Ref = K2>
+where
+    K1Ref: ?Sized + Hash,
+    K1: AsRef<K1Ref>,
+    K2Ref: ?Sized + Hash,
+    K2: AsRef<K2Ref>,
+{
+    outer: PhMap<K1, Group, K1Ref>,
+    inner_keys: Vec<K2>,
+    top_level_hashes: Vec<u64>,
+    values: Vec<Option<V>>,
+    _phantom: PhantomData<fn(&K2Ref)>,
+}
+
+impl<K1, K2, V, K1Ref, K2Ref> Default for Ph2Map<K1, K2, V, K1Ref, K2Ref>
+where
+    K1Ref: ?Sized + Hash,
+    K1: AsRef<K1Ref>,
+    K2Ref: ?Sized + Hash,
+    K2: AsRef<K2Ref>,
+{
+    fn default() -> Self {
+        Self {
+            outer: Default::default(),
+            inner_keys: vec![],
+            top_level_hashes: vec![],
+            values: vec![],
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<K1, K2, V, K1Ref, K2Ref> Ph2Map<K1, K2, V, K1Ref, K2Ref>
+where
+    K1Ref: ?Sized + Hash,
+    K1: AsRef<K1Ref>,
+    K2Ref: ?Sized + Hash,
+    K2: AsRef<K2Ref>,
+{
+    /// Inserts the entry, replacing the value if `(k1, k2)` is already in the map. The map is
+    /// only rebuilt if the entry is new, and then only the group for `k1`.
+    pub fn insert(&mut self, k1: K1, k2: K2, value: V) {
+        if let Some(existing) = self.get_mut(&k1, &k2) {
+            *existing = value;
+            return;
+        }
+
+        self.extend(std::iter::once((k1, k2, value)))
+    }
+
+    /// The total number of `(K1, K2)` entries.
+    pub fn len(&self) -> usize {
+        self.inner_keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner_keys.is_empty()
+    }
+
+    fn find_slot(&self, k1: &K1Ref, k2: &K2Ref) -> Option<usize> {
+        let group_idx = self.outer.find_index(k1)?;
+        let group = unsafe { self.outer.values.get_unchecked(group_idx).as_ref()? };
+
+        let hash = group.to_index.hasher().hash_one(k2, 0);
+        let idx = group.to_index.get_with_top_level_hash(k2, hash)?;
+        let slot = group.slots.start + idx;
+
+        (idx < group.slots.len() && self.top_level_hashes[slot] == hash).then_some(slot)
+    }
+
+    pub fn get<Q1, Q2>(&self, k1: &Q1, k2: &Q2) -> Option<&V>
+    where
+        Q1: ?Sized + AsRef<K1Ref>,
+        Q2: ?Sized + AsRef<K2Ref>,
+    {
+        let slot = self.find_slot(k1.as_ref(), k2.as_ref())?;
+        self.values[slot].as_ref()
+    }
+
+    pub fn get_mut<Q1, Q2>(&mut self, k1: &Q1, k2: &Q2) -> Option<&mut V>
+    where
+        Q1: ?Sized + AsRef<K1Ref>,
+        Q2: ?Sized + AsRef<K2Ref>,
+    {
+        let slot = self.find_slot(k1.as_ref(), k2.as_ref())?;
+        self.values[slot].as_mut()
+    }
+
+    pub fn contains_key<Q1, Q2>(&self, k1: &Q1, k2: &Q2) -> bool
+    where
+        Q1: ?Sized + AsRef<K1Ref>,
+        Q2: ?Sized + AsRef<K2Ref>,
+    {
+        self.find_slot(k1.as_ref(), k2.as_ref()).is_some()
+    }
+}
+
+impl<K1, K2, V, K1Ref, K2Ref> Extend<(K1, K2, V)> for Ph2Map<K1, K2, V, K1Ref, K2Ref>
+where
+    K1Ref: ?Sized + Hash,
+    K1: AsRef<K1Ref>,
+    K2Ref: ?Sized + Hash,
+    K2: AsRef<K2Ref>,
+{
+    /// Only the groups of outer keys which get new entries have their functions rebuilt. The
+    /// other groups are moved to their new place in the shared table as they are. An inner key
+    /// which is already in its group, or appears more than once, keeps the last value.
+    fn extend<I>(&mut self, entries: I)
+    where
+        I: IntoIterator<Item = (K1, K2, V)>,
+    {
+        let hasher = KeyHasher::default();
+
+        // Like `PhMap`, keys are considered equal if their hashes are equal.
+        let mut added = HashMap::<u64, (K1, Vec<(u64, K2, V)>)>::new();
+        for (k1, k2, value) in entries {
+            let (_, group_entries) = added
+                .entry(hasher.hash_one(k1.as_ref(), 0))
+                .or_insert_with(|| (k1, vec![]));
+
+            group_entries.push((hasher.hash_one(k2.as_ref(), 0), k2, value));
+        }
+
+        if added.is_empty() {
+            return;
+        }
+
+        let mut old_inner_keys = std::mem::take(&mut self.inner_keys).into_iter();
+        let mut old_values = std::mem::take(&mut self.values);
+        let old_hashes = std::mem::take(&mut self.top_level_hashes);
+
+        // Groups' keys are stored contiguously, in the same order as their slots.
+        let mut group_slots = (0..self.outer.values.len())
+            .filter(|&slot| self.outer.is_slot_occupied(slot))
+            .collect::<Vec<_>>();
+        group_slots.sort_by_key(|&slot| {
+            self.outer.values[slot]
+                .as_ref()
+                .map_or(0, |group| group.keys.start)
+        });
+
+        for outer_slot in group_slots {
+            let Some(group) = self.outer.values[outer_slot].take() else {
+                continue;
+            };
+            let k2s = old_inner_keys.by_ref().take(group.keys.len());
+
+            let k1 = &self.outer.keys[self.outer.key_indices[outer_slot] as usize];
+            let Some((_, new_entries)) = added.remove(&hasher.hash_one(k1.as_ref(), 0)) else {
+                let keys_start = self.inner_keys.len();
+                let slots_start = self.values.len();
+
+                self.inner_keys.extend(k2s);
+                self.values
+                    .extend(old_values[group.slots.clone()].iter_mut().map(Option::take));
+                self.top_level_hashes
+                    .extend_from_slice(&old_hashes[group.slots.clone()]);
+
+                self.outer.values[outer_slot] = Some(Group {
+                    to_index: group.to_index,
+                    keys: keys_start..self.inner_keys.len(),
+                    slots: slots_start..self.values.len(),
+                });
+                continue;
+            };
+
+            let mut merged = GroupEntries::default();
+            for k2 in k2s {
+                let idx = unsafe { group.to_index.get(k2.as_ref()).unwrap_unchecked() };
+                let slot = group.slots.start + idx;
+                let value = unsafe { old_values.get_unchecked_mut(slot).take().unwrap_unchecked() };
+
+                merged.push(old_hashes[slot], k2, value);
+            }
+            for (hash, k2, value) in new_entries {
+                merged.push(hash, k2, value);
+            }
+
+            self.outer.values[outer_slot] = Some(self.push_group(merged));
+        }
+
+        // Whatever is left is for outer keys which aren't in the map yet.
+        let new_groups = added
+            .into_values()
+            .map(|(k1, new_entries)| {
+                let mut merged = GroupEntries::default();
+                for (hash, k2, value) in new_entries {
+                    merged.push(hash, k2, value);
+                }
+
+                (k1, self.push_group(merged))
+            })
+            .collect::<Vec<_>>();
+
+        if !new_groups.is_empty() {
+            self.outer.extend(new_groups);
+        }
+    }
+}
+
+impl<K1, K2, V, K1Ref, K2Ref> Ph2Map<K1, K2, V, K1Ref, K2Ref>
+where
+    K1Ref: ?Sized + Hash,
+    K1: AsRef<K1Ref>,
+    K2Ref: ?Sized + Hash,
+    K2: AsRef<K2Ref>,
+{
+    /// Builds the function for a group and appends its keys and slots to the shared table.
+    fn push_group(&mut self, entries: GroupEntries<K2, V>) -> Group {
+        let entries = entries.entries;
+
+        let to_index = build_function(
+            entries.iter().map(|(_, k2, _)| k2.as_ref()).collect(),
+            Profile::default(),
+        );
+        let indices = entries
+            .iter()
+            .map(|(hash, k2, _)| {
+                to_index
+                    .get_with_top_level_hash(k2.as_ref(), *hash)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let keys_start = self.inner_keys.len();
+        let slots_start = self.values.len();
+        let num_slots = indices.iter().map(|idx| idx + 1).max().unwrap_or(0);
+
+        self.values.resize_with(slots_start + num_slots, || None);
+        self.top_level_hashes.resize(slots_start + num_slots, 0);
+
+        for (idx, (hash, k2, value)) in indices.into_iter().zip(entries) {
+            self.values[slots_start + idx] = Some(value);
+            self.top_level_hashes[slots_start + idx] = hash;
+            self.inner_keys.push(k2);
+        }
+
+        Group {
+            to_index,
+            keys: keys_start..self.inner_keys.len(),
+            slots: slots_start..self.values.len(),
+        }
+    }
+}
+
+/// The entries of one group, with one entry for each inner key hash.
+struct GroupEntries<K2, V> {
+    entries: Vec<(u64, K2, V)>,
+    positions: HashMap<u64, usize>,
+}
+
+impl<K2, V> Default for GroupEntries<K2, V> {
+    fn default() -> Self {
+        Self {
+            entries: vec![],
+            positions: HashMap::new(),
+        }
+    }
+}
+
+impl<K2, V> GroupEntries<K2, V> {
+    /// Adds an entry, replacing the entry with the same hash if there is one.
+    fn push(&mut self, hash: u64, k2: K2, value: V) {
+        match self.positions.entry(hash) {
+            Entry::Occupied(position) => self.entries[*position.get()] = (hash, k2, value),
+            Entry::Vacant(position) => {
+                position.insert(self.entries.len());
+                self.entries.push((hash, k2, value));
+            }
+        }
+    }
+}
+
+impl<K1, K2, V, K1Ref, K2Ref> FromIterator<(K1, K2, V)> for Ph2Map<K1, K2, V, K1Ref, K2Ref>
+where
+    K1Ref: ?Sized + Hash,
+    K1: AsRef<K1Ref>,
+    K2Ref: ?Sized + Hash,
+    K2: AsRef<K2Ref>,
+{
+    fn from_iter<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K1, K2, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(entries);
+        map
+    }
+}
@@ -0,0 +1,53 @@
+use std::borrow::Cow;
+
+use unicode_normalization::{
+    IsNormalized, UnicodeNormalization, is_nfc_quick, is_nfd_quick, is_nfkc_quick, is_nfkd_quick,
+};
+
+/// A Unicode normalization form applied to the keys of a [`PhStrMap`](crate::PhStrMap), so
+/// that canonically (or, for the compatibility forms, compatibly) equal strings are the same
+/// key. Set with [`MapOptions::normalization`](crate::MapOptions::normalization).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NormalizationForm {
+    #[default]
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl NormalizationForm {
+    /// `key` in this form, only allocating if it isn't already normalized.
+    pub(crate) fn normalize<'k>(self, key: &'k str) -> Cow<'k, str> {
+        let quick = match self {
+            Self::Nfc => is_nfc_quick(key.chars()),
+            Self::Nfd => is_nfd_quick(key.chars()),
+            Self::Nfkc => is_nfkc_quick(key.chars()),
+            Self::Nfkd => is_nfkd_quick(key.chars()),
+        };
+        if quick == IsNormalized::Yes {
+            return Cow::Borrowed(key);
+        }
+
+        Cow::Owned(match self {
+            Self::Nfc => key.nfc().collect(),
+            Self::Nfd => key.nfd().collect(),
+            Self::Nfkc => key.nfkc().collect(),
+            Self::Nfkd => key.nfkd().collect(),
+        })
+    }
+
+    pub(crate) fn normalize_cow(self, key: Cow<'static, str>) -> Cow<'static, str> {
+        match key {
+            Cow::Borrowed(key) => self.normalize(key),
+            Cow::Owned(key) => {
+                let normalized = match self.normalize(&key) {
+                    Cow::Borrowed(_) => None,
+                    Cow::Owned(normalized) => Some(normalized),
+                };
+
+                Cow::Owned(normalized.unwrap_or(key))
+            }
+        }
+    }
+}
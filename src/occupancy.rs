@@ -0,0 +1,33 @@
+/// One bit per slot, set if the slot holds a value. Lookups check this as well as the stored
+/// hash, so that a removed entry's hash can't be matched.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Occupancy {
+    words: Vec<u64>,
+}
+
+impl Occupancy {
+    /// Marks all `num_slots` slots as unoccupied.
+    pub(crate) fn reset(&mut self, num_slots: usize) {
+        self.words.clear();
+        self.words.resize(num_slots.div_ceil(64), 0);
+    }
+
+    pub(crate) fn insert(&mut self, idx: usize) {
+        self.words[idx / 64] |= 1 << (idx % 64);
+    }
+
+    pub(crate) fn remove(&mut self, idx: usize) {
+        self.words[idx / 64] &= !(1 << (idx % 64));
+    }
+
+    pub(crate) fn size_bytes(&self) -> usize {
+        self.words.capacity() * size_of::<u64>()
+    }
+
+    #[inline]
+    pub(crate) fn contains(&self, idx: usize) -> bool {
+        self.words
+            .get(idx / 64)
+            .is_some_and(|word| word & (1 << (idx % 64)) != 0)
+    }
+}
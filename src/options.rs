@@ -0,0 +1,80 @@
+#[cfg(feature = "unicode-normalization")]
+use crate::NormalizationForm;
+use crate::{Duplicates, KeyCompression};
+
+/// How a [`PhBytesMap`](crate::PhBytesMap), [`PhStrMap`](crate::PhStrMap) or
+/// [`PhPathMap`](crate::PhPathMap) stores and looks up its keys. Each setter can be chained
+/// with the others, and the result passed to e.g.
+/// [`PhStrMap::with_options`](crate::PhStrMap::with_options).
+///
+/// ```
+/// use ph_map::{Duplicates, MapOptions, PhStrMap};
+///
+/// let mut map = PhStrMap::with_options(
+///     MapOptions::new()
+///         .verify_keys(true)
+///         .duplicates(Duplicates::KeepLast),
+/// );
+/// map.extend([("a", 1), ("a", 2)]);
+/// assert_eq!(map.get("a"), Some(&2));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MapOptions {
+    pub(crate) compression: KeyCompression,
+    pub(crate) prefilter: Option<usize>,
+    pub(crate) verify_keys: bool,
+    pub(crate) duplicates: Duplicates,
+    #[cfg(feature = "unicode-normalization")]
+    pub(crate) normalization: Option<NormalizationForm>,
+}
+
+impl MapOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Picks the bytes of each key to hash using `compression`.
+    pub fn compression(self, compression: KeyCompression) -> Self {
+        Self {
+            compression,
+            ..self
+        }
+    }
+
+    /// Records the byte at `position` in every key, so that lookups for keys whose byte at
+    /// that position doesn't appear in any key of the same length are rejected without
+    /// hashing. This speeds up workloads where most lookups miss, such as checking every
+    /// identifier in a tokenizer against a keyword table.
+    pub fn prefilter(self, position: usize) -> Self {
+        Self {
+            prefilter: Some(position),
+            ..self
+        }
+    }
+
+    /// Whether lookups compare the whole key after finding its slot, so that a key which
+    /// only shares the discriminating range with a key in the map isn't found.
+    pub fn verify_keys(self, verify_keys: bool) -> Self {
+        Self {
+            verify_keys,
+            ..self
+        }
+    }
+
+    /// Handles keys that are added more than once as `duplicates` says.
+    pub fn duplicates(self, duplicates: Duplicates) -> Self {
+        Self { duplicates, ..self }
+    }
+
+    /// Converts keys to `form` before inserting or looking them up, so that strings which are
+    /// equal under `form` are the same key. Iteration returns the normalized keys. Lookups
+    /// only allocate for keys which aren't already normalized. Only used by
+    /// [`PhStrMap`](crate::PhStrMap).
+    #[cfg(feature = "unicode-normalization")]
+    pub fn normalization(self, form: NormalizationForm) -> Self {
+        Self {
+            normalization: Some(form),
+            ..self
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::hash::Hash;
+
+use ph::BuildSeededHasher;
+
+use crate::PhMap;
+
+/// A fixed ordering over the entries of a [`PhMap`], created by [`PhMap::order_by`].
+pub struct OrderedIndex<'a, KOwned, V> {
+    entries: Vec<(&'a KOwned, &'a V)>,
+}
+
+impl<'a, KOwned, V> OrderedIndex<'a, KOwned, V> {
+    pub(crate) fn new<KRef, H, S, F>(map: &'a PhMap<KOwned, V, KRef, H>, mut sort_key: F) -> Self
+    where
+        KRef: ?Sized + Hash,
+        KOwned: AsRef<KRef>,
+        H: BuildSeededHasher + Default,
+        S: Ord,
+        F: FnMut(&KOwned, &V) -> S,
+    {
+        let mut entries = map
+            .iter_indexed()
+            .map(|(_, key, value)| (key, value))
+            .collect::<Vec<_>>();
+        entries.sort_by_cached_key(|(key, value)| sort_key(key, value));
+
+        Self { entries }
+    }
+
+    /// Iterates over the entries in ascending order of their sort key.
+    pub fn iter_ordered(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&'a KOwned, &'a V)> + ExactSizeIterator + '_ {
+        self.entries.iter().copied()
+    }
+
+    /// The first `n` entries in ascending order of their sort key. Use [`std::cmp::Reverse`]
+    /// in the sort key to get the entries with the largest keys instead.
+    pub fn top_n(&self, n: usize) -> &[(&'a KOwned, &'a V)] {
+        &self.entries[..n.min(self.entries.len())]
+    }
+}
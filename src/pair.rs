@@ -0,0 +1,121 @@
+use std::{
+    borrow::Borrow,
+    hash::{Hash, Hasher},
+};
+
+use crate::PhMap;
+
+/// Key type used by [`PhPairMap`]. Fields are hashed in order, so a pair can be looked up
+/// from borrowed forms of each half.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pair<K1, K2>(pub K1, pub K2);
+
+impl<K1, K2> AsRef<Self> for Pair<K1, K2> {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+/// Borrowed form of [`Pair`], which must hash identically to it.
+struct PairRef<'a, Q1: ?Sized, Q2: ?Sized>(&'a Q1, &'a Q2);
+
+impl<Q1, Q2> Hash for PairRef<'_, Q1, Q2>
+where
+    Q1: ?Sized + Hash,
+    Q2: ?Sized + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        self.1.hash(state);
+    }
+}
+
+/// A map keyed by a pair of values, which can be looked up using borrowed forms of each
+/// half of the key (e.g. `(&str, u32)` for a `(String, u32)` key) without building an owned
+/// key.
+pub struct PhPairMap<K1, K2, V>
+where
+    K1: Hash,
+    K2: Hash,
+{
+    inner: PhMap<Pair<K1, K2>, V>,
+}
+
+impl<K1, K2, V> Default for PhPairMap<K1, K2, V>
+where
+    K1: Hash,
+    K2: Hash,
+{
+    fn default() -> Self {
+        Self {
+            inner: Default::default(),
+        }
+    }
+}
+
+impl<K1, K2, V> PhPairMap<K1, K2, V>
+where
+    K1: Hash,
+    K2: Hash,
+{
+    /// Inserts `value` for `(k1, k2)`, returning the previous value if there was one.
+    pub fn insert(&mut self, k1: K1, k2: K2, value: V) -> Option<V> {
+        self.inner.insert(Pair(k1, k2), value)
+    }
+
+    fn find_index<Q1, Q2>(&self, k1: &Q1, k2: &Q2) -> Option<usize>
+    where
+        K1: Borrow<Q1>,
+        K2: Borrow<Q2>,
+        Q1: ?Sized + Hash,
+        Q2: ?Sized + Hash,
+    {
+        self.inner.find_index(&PairRef(k1, k2))
+    }
+
+    pub fn get<Q1, Q2>(&self, k1: &Q1, k2: &Q2) -> Option<&V>
+    where
+        K1: Borrow<Q1>,
+        K2: Borrow<Q2>,
+        Q1: ?Sized + Hash,
+        Q2: ?Sized + Hash,
+    {
+        let idx = self.find_index(k1, k2)?;
+        self.inner.values[idx].as_ref()
+    }
+
+    pub fn get_mut<Q1, Q2>(&mut self, k1: &Q1, k2: &Q2) -> Option<&mut V>
+    where
+        K1: Borrow<Q1>,
+        K2: Borrow<Q2>,
+        Q1: ?Sized + Hash,
+        Q2: ?Sized + Hash,
+    {
+        let idx = self.find_index(k1, k2)?;
+        self.inner.values[idx].as_mut()
+    }
+
+    pub fn contains_key<Q1, Q2>(&self, k1: &Q1, k2: &Q2) -> bool
+    where
+        K1: Borrow<Q1>,
+        K2: Borrow<Q2>,
+        Q1: ?Sized + Hash,
+        Q2: ?Sized + Hash,
+    {
+        self.find_index(k1, k2).is_some()
+    }
+}
+
+impl<K1, K2, V> Extend<((K1, K2), V)> for PhPairMap<K1, K2, V>
+where
+    K1: Hash,
+    K2: Hash,
+{
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = ((K1, K2), V)>,
+    {
+        self.inner
+            .extend(kv.into_iter().map(|((k1, k2), v)| (Pair(k1, k2), v)))
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    ffi::OsStr,
+    fmt,
+    ops::Index,
+    path::{Path, PathBuf},
+};
+
+use crate::{BuildError, MapOptions, PhBytesMap};
+
+/// A map keyed by paths, for file-path routing tables. Paths are stored and hashed as their
+/// OS-native encoding (see [`OsStr::as_encoded_bytes`]) in a [`PhBytesMap`], so they get the
+/// same per-length shards and key compression as a [`PhStrMap`](crate::PhStrMap).
+///
+/// Paths are compared byte by byte rather than by component, so `a/b` and `a//b` are
+/// different keys.
+#[derive(Clone)]
+pub struct PhPathMap<V> {
+    inner: PhBytesMap<V>,
+}
+
+impl<V> Default for PhPathMap<V> {
+    fn default() -> Self {
+        Self {
+            inner: PhBytesMap::default(),
+        }
+    }
+}
+
+/// The bytes of a key which was inserted from an `OsStr`.
+#[inline]
+fn to_path(key: &[u8]) -> &Path {
+    // SAFETY: every key of `inner` comes from `as_encoded_bytes` or `into_encoded_bytes` of a
+    // path, and `PhBytesMap` hands keys back exactly as they were inserted, so `key` is the
+    // whole encoding of an `OsStr` rather than a part of one.
+    Path::new(unsafe { OsStr::from_encoded_bytes_unchecked(key) })
+}
+
+#[inline]
+fn to_bytes<K>(key: &K) -> &[u8]
+where
+    K: ?Sized + AsRef<Path>,
+{
+    key.as_ref().as_os_str().as_encoded_bytes()
+}
+
+impl<V> PhPathMap<V> {
+    pub fn with_options(options: MapOptions) -> Self {
+        Self {
+            inner: PhBytesMap::with_options(options),
+        }
+    }
+
+    pub fn options(&self) -> MapOptions {
+        self.inner.options()
+    }
+
+    /// See [`PhBytesMap::insert`].
+    pub fn insert(&mut self, key: PathBuf, value: V) -> Option<V> {
+        self.inner
+            .insert(key.into_os_string().into_encoded_bytes(), value)
+    }
+
+    /// See [`PhBytesMap::try_extend`].
+    pub fn try_extend<KV>(&mut self, kv: KV) -> Result<(), BuildError>
+    where
+        KV: IntoIterator<Item = (PathBuf, V)>,
+    {
+        self.inner.try_extend(
+            kv.into_iter()
+                .map(|(k, v)| (k.into_os_string().into_encoded_bytes(), v)),
+        )
+    }
+
+    /// Iterates in the same order as [`PhStrMap::iter`](crate::PhStrMap::iter).
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &V)> {
+        self.inner.iter().map(|(key, value)| (to_path(key), value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Path> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+    pub fn get<K>(&self, key: &K) -> Option<&V>
+    where
+        K: ?Sized + AsRef<Path>,
+    {
+        self.inner.get(to_bytes(key))
+    }
+
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+    pub fn contains_key<K>(&self, key: &K) -> bool
+    where
+        K: ?Sized + AsRef<Path>,
+    {
+        self.inner.contains_key(to_bytes(key))
+    }
+
+    #[inline]
+    #[cfg_attr(all(feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+    pub fn get_mut<K>(&mut self, key: &K) -> Option<&mut V>
+    where
+        K: ?Sized + AsRef<Path>,
+    {
+        self.inner.get_mut(to_bytes(key))
+    }
+
+    pub fn remove<K>(&mut self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<Path>,
+    {
+        self.inner.remove(to_bytes(key))
+    }
+
+    /// See [`PhBytesMap::retain`].
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Path, &mut V) -> bool,
+    {
+        self.inner.retain(|key, value| f(to_path(key), value))
+    }
+}
+
+impl<V> Extend<(PathBuf, V)> for PhPathMap<V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (PathBuf, V)>,
+    {
+        self.inner.extend(
+            kv.into_iter()
+                .map(|(k, v)| (k.into_os_string().into_encoded_bytes(), v)),
+        )
+    }
+}
+
+/// Borrows the keys rather than copying them, like the equivalent impl for
+/// [`PhStrMap`](crate::PhStrMap).
+impl<V> Extend<(&'static Path, V)> for PhPathMap<V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (&'static Path, V)>,
+    {
+        self.inner
+            .extend(kv.into_iter().map(|(k, v)| (to_bytes(k), v)))
+    }
+}
+
+impl<V> FromIterator<(PathBuf, V)> for PhPathMap<V> {
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (PathBuf, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<V> FromIterator<(&'static Path, V)> for PhPathMap<V> {
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (&'static Path, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<K, V> Index<&K> for PhPathMap<V>
+where
+    K: ?Sized + AsRef<Path>,
+{
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key not in map")
+    }
+}
+
+impl<V> fmt::Debug for PhPathMap<V>
+where
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
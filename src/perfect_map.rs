@@ -0,0 +1,132 @@
+use std::hash::Hash;
+
+use ph::BuildSeededHasher;
+
+use crate::{PhBytesMap, PhMap, PhStrMap, StrValues};
+
+/// A read-only lookup table keyed by `Q`, so that code can accept any of this crate's maps
+/// (or a [`phf::Map`]) without committing to one concrete type.
+///
+/// Iteration is over values only, as not every implementation retains its original keys.
+pub trait PerfectMap<Q: ?Sized> {
+    type Value;
+    type Values<'a>: Iterator<Item = &'a Self::Value>
+    where
+        Self: 'a,
+        Self::Value: 'a;
+
+    fn get(&self, key: &Q) -> Option<&Self::Value>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn contains_key(&self, key: &Q) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn values(&self) -> Self::Values<'_>;
+}
+
+impl<KOwned, V, KRef, H> PerfectMap<KRef> for PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    type Value = V;
+    type Values<'a>
+        = std::iter::Flatten<std::slice::Iter<'a, Option<V>>>
+    where
+        Self: 'a;
+
+    fn get(&self, key: &KRef) -> Option<&V> {
+        let idx = self.find_index(key)?;
+        self.values[idx].as_ref()
+    }
+
+    fn len(&self) -> usize {
+        PhMap::len(self)
+    }
+
+    fn contains_key(&self, key: &KRef) -> bool {
+        self.find_index(key).is_some()
+    }
+
+    fn values(&self) -> Self::Values<'_> {
+        self.values.iter().flatten()
+    }
+}
+
+impl<V> PerfectMap<str> for PhStrMap<V> {
+    type Value = V;
+    type Values<'a>
+        = StrValues<'a, V>
+    where
+        Self: 'a;
+
+    fn get(&self, key: &str) -> Option<&V> {
+        PhStrMap::get(self, key)
+    }
+
+    fn len(&self) -> usize {
+        PhStrMap::len(self)
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        PhStrMap::contains_key(self, key)
+    }
+
+    fn values(&self) -> Self::Values<'_> {
+        self.inner.values()
+    }
+}
+
+impl<V> PerfectMap<[u8]> for PhBytesMap<V> {
+    type Value = V;
+    type Values<'a>
+        = StrValues<'a, V>
+    where
+        Self: 'a;
+
+    fn get(&self, key: &[u8]) -> Option<&V> {
+        PhBytesMap::get(self, key)
+    }
+
+    fn len(&self) -> usize {
+        PhBytesMap::len(self)
+    }
+
+    fn contains_key(&self, key: &[u8]) -> bool {
+        PhBytesMap::contains_key(self, key)
+    }
+
+    fn values(&self) -> Self::Values<'_> {
+        StrValues {
+            shards: self.shards.iter(),
+            values: [].iter(),
+        }
+    }
+}
+
+impl<V> PerfectMap<str> for phf::Map<&'static str, V> {
+    type Value = V;
+    type Values<'a>
+        = phf::map::Values<'a, &'static str, V>
+    where
+        Self: 'a;
+
+    fn get(&self, key: &str) -> Option<&V> {
+        phf::Map::get(self, key)
+    }
+
+    fn len(&self) -> usize {
+        phf::Map::len(self)
+    }
+
+    fn values(&self) -> Self::Values<'_> {
+        phf::Map::values(self)
+    }
+}
@@ -0,0 +1,61 @@
+use std::hash::Hasher;
+
+use ph::BuildSeededHasher;
+
+/// A seeded hasher which gives the same results on every platform. Integers are hashed as
+/// their little-endian bytes and `usize`/`isize` are widened to 64 bits before hashing, so
+/// keys resolve to the same slots regardless of architecture, endianness or pointer width.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildPortableHasher;
+
+impl BuildSeededHasher for BuildPortableHasher {
+    type Hasher = PortableHasher;
+
+    fn build_hasher(&self, seed: u32) -> PortableHasher {
+        PortableHasher { state: seed.into() }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PortableHasher {
+    state: u64,
+}
+
+macro_rules! write_le {
+    ($($name:ident: $t:ty),*) => {
+        $(
+            fn $name(&mut self, i: $t) {
+                self.write(&i.to_le_bytes());
+            }
+        )*
+    };
+}
+
+impl Hasher for PortableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.state = rapidhash::rapidhash_seeded(bytes, self.state);
+    }
+
+    write_le!(
+        write_u16: u16,
+        write_u32: u32,
+        write_u64: u64,
+        write_u128: u128,
+        write_i16: i16,
+        write_i32: i32,
+        write_i64: i64,
+        write_i128: i128
+    );
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.state
+    }
+}
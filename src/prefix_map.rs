@@ -0,0 +1,122 @@
+use std::fmt;
+
+use crate::{MapOptions, PhStrMap, StrIter};
+
+/// A map answering longest-prefix-match queries, for routing tables and tokenizers. Keys are
+/// stored in a [`PhStrMap`], which already keeps a perfect hash function per key length, so a
+/// query only looks up the prefixes whose length some key has, longest first.
+///
+/// Lookups compare the whole key, as with [`MapOptions::verify_keys`], since most prefixes of a
+/// query aren't keys.
+#[derive(Clone)]
+pub struct PhPrefixMap<V> {
+    inner: PhStrMap<V>,
+}
+
+impl<V> Default for PhPrefixMap<V> {
+    fn default() -> Self {
+        Self {
+            inner: PhStrMap::with_options(MapOptions::new().verify_keys(true)),
+        }
+    }
+}
+
+impl<V> PhPrefixMap<V> {
+    /// Inserts `value`, returning the old value if `key` was already in the map.
+    pub fn insert(&mut self, key: String, value: V) -> Option<V> {
+        self.inner.insert(key, value)
+    }
+
+    /// The longest key which is a prefix of `query`, and its value.
+    pub fn longest_prefix<'q>(&self, query: &'q str) -> Option<(&'q str, &V)> {
+        self.prefixes(query).next()
+    }
+
+    /// Every key which is a prefix of `query` with its value, longest first.
+    pub fn prefixes<'q>(&self, query: &'q str) -> impl Iterator<Item = (&'q str, &V)> {
+        self.inner
+            .inner
+            .lengths_up_to(query.len())
+            .filter_map(move |len| {
+                let prefix = query.get(..len)?;
+
+                Some((prefix, self.inner.get(prefix)?))
+            })
+    }
+
+    pub fn get<K>(&self, key: &K) -> Option<&V>
+    where
+        K: ?Sized + AsRef<str>,
+    {
+        self.inner.get(key)
+    }
+
+    pub fn remove<K>(&mut self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<str>,
+    {
+        self.inner.remove(key)
+    }
+
+    /// Iterates in the same order as [`PhStrMap::iter`].
+    pub fn iter(&self) -> StrIter<'_, V> {
+        self.inner.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<V> Extend<(String, V)> for PhPrefixMap<V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (String, V)>,
+    {
+        self.inner.extend(kv)
+    }
+}
+
+impl<V> Extend<(&'static str, V)> for PhPrefixMap<V> {
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (&'static str, V)>,
+    {
+        self.inner.extend(kv)
+    }
+}
+
+impl<V> FromIterator<(String, V)> for PhPrefixMap<V> {
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (String, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<V> FromIterator<(&'static str, V)> for PhPrefixMap<V> {
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (&'static str, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<V> fmt::Debug for PhPrefixMap<V>
+where
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
@@ -0,0 +1,108 @@
+use ph::phast::{Params, bits_per_seed_to_100_bucket_size};
+use ph::seeds::BitsFast;
+
+use crate::{MAX_SEED_BITS, seed_bits};
+
+/// Bits per seed used by [`Profile::MinMemory`], regardless of the number of keys.
+const MIN_MEMORY_SEED_BITS: u8 = 8;
+
+/// A preset trading off memory use, lookup speed and build speed. Each profile selects the
+/// bits per seed and bucket size of the underlying PHast function, the width of the hash
+/// fingerprint stored per slot, and whether slots use a minimal or sparse layout. Set with
+/// [`PhMap::with_profile`](crate::PhMap::with_profile).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Profile {
+    /// Seed size scaled to the number of keys, with PHast's recommended bucket size, 64-bit
+    /// fingerprints and a sparse layout.
+    #[default]
+    Balanced,
+    /// Small seeds and large buckets, so that fewer, smaller seeds are stored, with 16-bit
+    /// fingerprints and a minimal layout. Builds are slower, and a key which isn't in the map
+    /// is more likely to be found.
+    MinMemory,
+    /// The largest seeds with small buckets, so that as many keys as possible are placed by
+    /// the first level of the function, with 32-bit fingerprints to keep more of them in
+    /// cache. Uses the most memory for the function.
+    MaxLookupSpeed,
+    /// The largest seeds with PHast's recommended bucket size, so that seeds are found
+    /// quickly, and a sparse layout so that no keys need to be moved after the build.
+    FastBuild,
+    /// Parameters set by a [`PhMapBuilder`](crate::PhMapBuilder). Each one which isn't set is
+    /// chosen as for `base`, as are the fingerprint width and slot layout.
+    Custom {
+        bits_per_seed: Option<u8>,
+        bucket_size100: Option<u16>,
+        base: &'static Profile,
+    },
+}
+
+impl Profile {
+    pub(crate) fn params(self, num_keys: usize) -> Params<BitsFast> {
+        let (bits, bucket_size100) = self.unclamped_params(num_keys);
+
+        // Custom parameters may be set directly rather than through the builder, which clamps
+        // them, so keep them within what PHast supports here too.
+        Params::new(
+            BitsFast(bits.clamp(1, MAX_SEED_BITS)),
+            bucket_size100.max(1),
+        )
+    }
+
+    /// Bits of each key's hash stored in its slot. A lookup for a key which isn't in the map
+    /// finds the value of a key sharing its slot with a probability of `2^-bits`.
+    pub fn fingerprint_bits(self) -> u8 {
+        match self {
+            Self::Balanced | Self::FastBuild => 64,
+            Self::MaxLookupSpeed => 32,
+            Self::MinMemory => 16,
+            Self::Custom { base, .. } => base.fingerprint_bits(),
+        }
+    }
+
+    /// Whether maps have exactly one slot per key, rather than keeping the holes PHast leaves
+    /// between slots. A minimal layout stores fewer slots, but lookups of keys placed past the
+    /// last slot go through an extra table.
+    pub fn is_minimal(self) -> bool {
+        match self {
+            Self::MinMemory => true,
+            Self::Balanced | Self::MaxLookupSpeed | Self::FastBuild => false,
+            Self::Custom { base, .. } => base.is_minimal(),
+        }
+    }
+
+    /// Bits per seed and bucket size (times 100) for a function over `num_keys` keys.
+    fn unclamped_params(self, num_keys: usize) -> (u8, u16) {
+        match self {
+            Self::Balanced => {
+                let bits = seed_bits(num_keys);
+                (bits, bits_per_seed_to_100_bucket_size(bits))
+            }
+            Self::MinMemory => {
+                let bits = seed_bits(num_keys).min(MIN_MEMORY_SEED_BITS);
+                (
+                    bits,
+                    bits_per_seed_to_100_bucket_size(bits).saturating_mul(5) / 4,
+                )
+            }
+            Self::MaxLookupSpeed => (
+                MAX_SEED_BITS,
+                bits_per_seed_to_100_bucket_size(MAX_SEED_BITS) * 3 / 4,
+            ),
+            Self::FastBuild => (
+                MAX_SEED_BITS,
+                bits_per_seed_to_100_bucket_size(MAX_SEED_BITS),
+            ),
+            Self::Custom {
+                bits_per_seed,
+                bucket_size100,
+                base,
+            } => {
+                let (base_bits, base_bucket_size100) = base.unclamped_params(num_keys);
+                (
+                    bits_per_seed.unwrap_or(base_bits),
+                    bucket_size100.unwrap_or(base_bucket_size100),
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+/// Cumulative counters for the rebuilds a [`PhMap`](crate::PhMap) has performed over its
+/// lifetime, returned by [`PhMap::rebuild_stats`](crate::PhMap::rebuild_stats). Large numbers
+/// relative to the size of the map mean it's being built up by many small insertions, each of
+/// which rebuilds every existing entry, and should use a batched `extend` instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RebuildStats {
+    pub rebuilds: u64,
+    /// Existing entries moved into the rebuilt map, not counting the new entries.
+    pub entries_moved: u64,
+    /// Size of the existing keys hashed again during rebuilds.
+    pub bytes_rehashed: u64,
+}
+
+impl RebuildStats {
+    pub(crate) fn record(&mut self, entries_moved: usize, bytes_rehashed: usize) {
+        self.rebuilds += 1;
+        self.entries_moved += entries_moved as u64;
+        self.bytes_rehashed += bytes_rehashed as u64;
+    }
+}
@@ -0,0 +1,211 @@
+use std::{collections::HashSet, fmt, hash::Hash, marker::PhantomData};
+
+use ph::BuildSeededHasher;
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, DeserializeSeed, MapAccess, Visitor},
+};
+
+use crate::{PhMap, PhStrMap};
+
+/// Serializes as a map from the original keys to the values, in iteration order.
+impl<V> Serialize for PhStrMap<V>
+where
+    V: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// Deserializes from a map with string keys, building the map once every entry has been
+/// read. The map is created with the default options, and duplicate keys are an error.
+impl<'de, V> Deserialize<'de> for PhStrMap<V>
+where
+    V: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(StrMapVisitor(PhantomData))
+    }
+}
+
+struct StrMapVisitor<V>(PhantomData<fn() -> V>);
+
+impl<'de, V> Visitor<'de> for StrMapVisitor<V>
+where
+    V: Deserialize<'de>,
+{
+    type Value = PhStrMap<V>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map with string keys")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        // Don't trust the size hint with more than a page or so of entries up front.
+        let mut entries = Vec::with_capacity(access.size_hint().unwrap_or(0).min(4096));
+        while let Some(entry) = access.next_entry::<String, V>()? {
+            entries.push(entry);
+        }
+
+        let mut map = PhStrMap::default();
+        map.try_extend(entries).map_err(de::Error::custom)?;
+
+        Ok(map)
+    }
+}
+
+/// Serializes as a map from the keys to the values, in insertion order.
+impl<KOwned, V, KRef, H> Serialize for PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef> + Serialize,
+    V: Serialize,
+    H: BuildSeededHasher + Default,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// Deserializes from a map, building the map once every entry has been read. The map is
+/// created with the default profile, and duplicate keys are an error.
+impl<'de, KOwned, V, KRef, H> Deserialize<'de> for PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash + Eq,
+    KOwned: AsRef<KRef> + Deserialize<'de>,
+    V: Deserialize<'de>,
+    H: BuildSeededHasher + Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
+
+struct MapVisitor<M>(PhantomData<fn() -> M>);
+
+impl<'de, KOwned, V, KRef, H> Visitor<'de> for MapVisitor<PhMap<KOwned, V, KRef, H>>
+where
+    KRef: ?Sized + Hash + Eq,
+    KOwned: AsRef<KRef> + Deserialize<'de>,
+    V: Deserialize<'de>,
+    H: BuildSeededHasher + Default,
+{
+    type Value = PhMap<KOwned, V, KRef, H>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, access: A) -> Result<Self::Value, A::Error> {
+        let entries = read_entries::<_, _, KRef, _>(access)?;
+
+        let mut map = PhMap::default();
+        map.try_extend(entries).map_err(de::Error::custom)?;
+
+        Ok(map)
+    }
+}
+
+/// Reads every entry of a map, failing if a key appears more than once, as a function can't be
+/// built over the same key twice.
+fn read_entries<'de, KOwned, V, KRef, A>(mut access: A) -> Result<Vec<(KOwned, V)>, A::Error>
+where
+    KRef: ?Sized + Hash + Eq,
+    KOwned: AsRef<KRef> + Deserialize<'de>,
+    V: Deserialize<'de>,
+    A: MapAccess<'de>,
+{
+    let mut entries = Vec::<(KOwned, V)>::with_capacity(access.size_hint().unwrap_or(0).min(4096));
+    while let Some(entry) = access.next_entry()? {
+        entries.push(entry);
+    }
+
+    let mut seen = HashSet::with_capacity(entries.len());
+    if let Some(i) = entries.iter().position(|(k, _)| !seen.insert(k.as_ref())) {
+        return Err(de::Error::custom(format_args!(
+            "key of entry {i} appears earlier in the map"
+        )));
+    }
+
+    Ok(entries)
+}
+
+/// Deserializes a map into an existing [`PhMap`] or [`PhStrMap`], replacing its entries, for
+/// maps which are reloaded often. Unlike [`Deserialize`], the map keeps its profile or
+/// options, and it's rebuilt in the allocations it already has for its keys, values and
+/// hashes rather than new ones. If the input can't be read, the map is left as it was.
+pub struct DeserializeInto<'a, M>(pub &'a mut M);
+
+impl<'de, KOwned, V, KRef, H> DeserializeSeed<'de>
+    for DeserializeInto<'_, PhMap<KOwned, V, KRef, H>>
+where
+    KRef: ?Sized + Hash + Eq,
+    KOwned: AsRef<KRef> + Deserialize<'de>,
+    V: Deserialize<'de>,
+    H: BuildSeededHasher + Default,
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+/// The map is cleared once every entry has been read, so if it then can't be built, it's left
+/// empty.
+impl<'de, KOwned, V, KRef, H> Visitor<'de> for DeserializeInto<'_, PhMap<KOwned, V, KRef, H>>
+where
+    KRef: ?Sized + Hash + Eq,
+    KOwned: AsRef<KRef> + Deserialize<'de>,
+    V: Deserialize<'de>,
+    H: BuildSeededHasher + Default,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, access: A) -> Result<(), A::Error> {
+        let entries = read_entries::<_, _, KRef, _>(access)?;
+
+        self.0.clear();
+        self.0.try_extend(entries).map_err(de::Error::custom)
+    }
+}
+
+impl<'de, V> DeserializeSeed<'de> for DeserializeInto<'_, PhStrMap<V>>
+where
+    V: Deserialize<'de>,
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+/// As for a [`PhMap`], the map is cleared once every entry has been read, so if it then can't
+/// be built, it's left empty. The shards for the lengths of the old keys are kept, so new keys
+/// of the same lengths reuse their allocations.
+impl<'de, V> Visitor<'de> for DeserializeInto<'_, PhStrMap<V>>
+where
+    V: Deserialize<'de>,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map with string keys")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<(), A::Error> {
+        let mut entries = Vec::with_capacity(access.size_hint().unwrap_or(0).min(4096));
+        while let Some(entry) = access.next_entry::<String, V>()? {
+            entries.push(entry);
+        }
+
+        self.0.clear();
+        self.0.try_extend(entries).map_err(de::Error::custom)
+    }
+}
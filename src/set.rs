@@ -0,0 +1,257 @@
+use std::{collections::HashSet, fmt, hash::Hash, marker::PhantomData};
+
+use ph::BuildSeededHasher;
+
+use crate::{
+    BuildError, KeyHasher, Profile, SlotLayout, build_failed, checked_num_keys, empty_function,
+    find_slot, fingerprints::Fingerprints, occupancy::Occupancy, slot_function::SlotFunction,
+};
+
+/// A set built on the same perfect hash functions as [`PhMap`](crate::PhMap), but with no
+/// value storage. Each slot only has the hash used to reject keys which aren't in the set.
+pub struct PhSet<KOwned, KRef = KOwned>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    keys: Vec<KOwned>,
+    top_level_hashes: Fingerprints,
+    occupied: Occupancy,
+    to_index: SlotFunction,
+    profile: Profile,
+    _phantom: PhantomData<fn(&KRef)>,
+}
+
+impl<KOwned, KRef> Default for PhSet<KOwned, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn default() -> Self {
+        Self {
+            keys: vec![],
+            top_level_hashes: Fingerprints::default(),
+            occupied: Occupancy::default(),
+            to_index: SlotFunction::sparse(empty_function::<KRef, KeyHasher>()),
+            profile: Profile::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<KOwned, KRef> PhSet<KOwned, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    /// Creates an empty set whose hash function is built with the parameters of `profile`.
+    pub fn with_profile(profile: Profile) -> Self {
+        Self {
+            top_level_hashes: Fingerprints::new(profile.fingerprint_bits()),
+            profile,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn contains<K>(&self, key: &K) -> bool
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let key = key.as_ref();
+        let hash = KeyHasher::default().hash_one(key, 0);
+
+        find_slot(
+            &self.to_index,
+            &self.top_level_hashes,
+            &self.occupied,
+            key,
+            hash,
+        )
+        .is_some()
+    }
+
+    /// Adds `key`, returning `false` without rebuilding if it was already in the set.
+    pub fn insert(&mut self, key: KOwned) -> bool {
+        if self.contains(&key) {
+            return false;
+        }
+
+        self.extend(std::iter::once(key));
+        true
+    }
+
+    /// Like [`Extend::extend`], but returns an error instead of panicking if the set can't
+    /// be built. On error, the set is unchanged.
+    pub fn try_extend<I>(&mut self, keys: I) -> Result<(), BuildError>
+    where
+        I: IntoIterator<Item = KOwned>,
+    {
+        // Keys are told apart by their hashes, so keys with the same hash are the same key.
+        let mut seen = HashSet::new();
+        let new = keys
+            .into_iter()
+            .filter(|key| {
+                !self.contains(key) && seen.insert(KeyHasher::default().hash_one(key.as_ref(), 0))
+            })
+            .collect::<Vec<_>>();
+        if new.is_empty() {
+            return Ok(());
+        }
+
+        self.rebuild(new)
+    }
+
+    /// Like [`PhMap`](crate::PhMap)'s rebuild, everything which can fail is done before the
+    /// set is changed.
+    #[cold]
+    #[inline(never)]
+    fn rebuild(&mut self, new: Vec<KOwned>) -> Result<(), BuildError> {
+        let num_keys = checked_num_keys(self.keys.len(), new.len())?;
+
+        let hashes = self
+            .keys
+            .iter()
+            .chain(&new)
+            .map(|key| KeyHasher::default().hash_one(key.as_ref(), 0))
+            .collect::<Vec<_>>();
+        let layout = SlotLayout::new(
+            self.keys.iter().chain(&new).map(|k| k.as_ref()),
+            &hashes,
+            self.profile,
+            false,
+        )?;
+
+        self.keys
+            .try_reserve_exact(new.len())
+            .map_err(BuildError::capacity_overflow(num_keys))?;
+        self.top_level_hashes
+            .try_reserve_exact(layout.num_slots.saturating_sub(self.top_level_hashes.len()))
+            .map_err(BuildError::capacity_overflow(layout.num_slots))?;
+
+        self.keys.extend(new);
+        self.to_index = layout.to_index;
+        self.top_level_hashes.reset(layout.num_slots);
+        self.occupied.reset(layout.num_slots);
+
+        for (idx, hash) in layout.indices.into_iter().zip(hashes) {
+            self.occupied.insert(idx);
+            // Every index is below `num_slots`.
+            unsafe { self.top_level_hashes.set_unchecked(idx, hash) };
+        }
+
+        Ok(())
+    }
+
+    /// Iterates over the keys in insertion order.
+    pub fn iter(&self) -> std::slice::Iter<'_, KOwned> {
+        self.keys.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Keys in either set, with the keys of `self` first.
+    pub fn union<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a KOwned> {
+        self.iter().chain(other.difference(self))
+    }
+
+    /// Keys in both sets, in the order of `self`.
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a KOwned> {
+        self.iter().filter(|key| other.contains(*key))
+    }
+
+    /// Keys in `self` but not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a KOwned> {
+        self.iter().filter(|key| !other.contains(*key))
+    }
+
+    /// Keys in exactly one of the sets, with the keys of `self` first.
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a KOwned> {
+        self.difference(other).chain(other.difference(self))
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.iter().all(|key| other.contains(key))
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
+
+/// Keys which are already in the set, or appear more than once, are only added once.
+impl<KOwned, KRef> Extend<KOwned> for PhSet<KOwned, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn extend<I>(&mut self, keys: I)
+    where
+        I: IntoIterator<Item = KOwned>,
+    {
+        if let Err(e) = self.try_extend(keys) {
+            build_failed(e);
+        }
+    }
+}
+
+impl<KOwned, KRef> FromIterator<KOwned> for PhSet<KOwned, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn from_iter<I>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = KOwned>,
+    {
+        let mut set = Self::default();
+        set.extend(keys);
+        set
+    }
+}
+
+impl<'a, KOwned, KRef> IntoIterator for &'a PhSet<KOwned, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    type Item = &'a KOwned;
+    type IntoIter = std::slice::Iter<'a, KOwned>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Rebuilds the hash function, as it can't be cloned.
+impl<KOwned, KRef> Clone for PhSet<KOwned, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef> + Clone,
+{
+    fn clone(&self) -> Self {
+        let mut set = Self::with_profile(self.profile);
+        set.extend(self.iter().cloned());
+        set
+    }
+}
+
+impl<KOwned, KRef> fmt::Debug for PhSet<KOwned, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
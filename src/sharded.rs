@@ -0,0 +1,200 @@
+use std::{fmt, hash::Hash};
+
+use ph::BuildSeededHasher;
+
+use crate::{KeyHasher, PhMap};
+
+/// Seed of the hash which picks a key's shard. This differs from the seed used inside each
+/// shard, so that the keys of a shard are still spread evenly by its own function.
+const SHARD_SEED: u32 = 1;
+
+/// Number of shards used by [`PhShardedMap::default`].
+const DEFAULT_SHARDS: usize = 16;
+
+/// A map split into independent [`PhMap`]s by a hash of the key, for key sets which are too
+/// large to build a single function over at once. Each rebuild only touches the shards which
+/// get new keys, and the shards can be built on separate threads with
+/// [`PhShardedMap::extend_parallel`]. Lookups hash the key once more to pick its shard.
+pub struct PhShardedMap<KOwned, V, KRef = KOwned>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    shards: Box<[PhMap<KOwned, V, KRef>]>,
+}
+
+impl<KOwned, V, KRef> Default for PhShardedMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn default() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+}
+
+impl<KOwned, V, KRef> PhShardedMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    /// Creates an empty map with `num_shards` shards, or one if `num_shards` is zero.
+    pub fn with_shards(num_shards: usize) -> Self {
+        Self {
+            shards: (0..num_shards.max(1)).map(|_| PhMap::default()).collect(),
+        }
+    }
+
+    #[inline]
+    fn shard_index(&self, key: &KRef) -> usize {
+        let hash = KeyHasher::default().hash_one(key, SHARD_SEED);
+
+        // Maps the hash onto `0..len` without a division.
+        ((hash as u128 * self.shards.len() as u128) >> 64) as usize
+    }
+
+    #[inline]
+    fn shard(&self, key: &KRef) -> &PhMap<KOwned, V, KRef> {
+        // `shard_index` is always less than the number of shards.
+        unsafe { self.shards.get_unchecked(self.shard_index(key)) }
+    }
+
+    #[inline]
+    fn shard_mut(&mut self, key: &KRef) -> &mut PhMap<KOwned, V, KRef> {
+        let idx = self.shard_index(key);
+
+        unsafe { self.shards.get_unchecked_mut(idx) }
+    }
+
+    /// Inserts `value`, returning the old value if `key` was already in the map. Only the
+    /// key's shard is rebuilt.
+    pub fn insert(&mut self, key: KOwned, value: V) -> Option<V> {
+        self.shard_mut(key.as_ref()).insert(key, value)
+    }
+
+    #[inline]
+    pub fn get<K>(&self, key: &K) -> Option<&V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.shard(key.as_ref()).get(key)
+    }
+
+    #[inline]
+    pub fn get_mut<K>(&mut self, key: &K) -> Option<&mut V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.shard_mut(key.as_ref()).get_mut(key)
+    }
+
+    #[inline]
+    pub fn contains_key<K>(&self, key: &K) -> bool
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.shard(key.as_ref()).contains_key(key)
+    }
+
+    pub fn remove<K>(&mut self, key: &K) -> Option<V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.shard_mut(key.as_ref()).remove(key)
+    }
+
+    /// Iterates over the entries shard by shard.
+    pub fn iter(&self) -> impl Iterator<Item = (&KOwned, &V)> {
+        self.shards.iter().flat_map(PhMap::iter)
+    }
+
+    pub fn shards(&self) -> &[PhMap<KOwned, V, KRef>] {
+        &self.shards
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(PhMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(PhMap::is_empty)
+    }
+
+    /// Splits `kv` into one batch per shard.
+    fn partition<KV>(&self, kv: KV) -> Vec<Vec<(KOwned, V)>>
+    where
+        KV: IntoIterator<Item = (KOwned, V)>,
+    {
+        let mut batches = (0..self.shards.len()).map(|_| vec![]).collect::<Vec<_>>();
+        for (key, value) in kv {
+            batches[self.shard_index(key.as_ref())].push((key, value));
+        }
+
+        batches
+    }
+
+    /// Like [`Extend::extend`], but rebuilds the shards on separate threads, one for each
+    /// shard which gets new keys.
+    pub fn extend_parallel<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (KOwned, V)>,
+        KOwned: Send,
+        V: Send,
+    {
+        let batches = self.partition(kv);
+
+        std::thread::scope(|scope| {
+            for (shard, batch) in self.shards.iter_mut().zip(batches) {
+                if !batch.is_empty() {
+                    scope.spawn(move || shard.extend(batch));
+                }
+            }
+        });
+    }
+}
+
+/// Rebuilds each shard which gets new keys once.
+impl<KOwned, V, KRef> Extend<(KOwned, V)> for PhShardedMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn extend<KV>(&mut self, kv: KV)
+    where
+        KV: IntoIterator<Item = (KOwned, V)>,
+    {
+        let batches = self.partition(kv);
+
+        for (shard, batch) in self.shards.iter_mut().zip(batches) {
+            if !batch.is_empty() {
+                shard.extend(batch);
+            }
+        }
+    }
+}
+
+impl<KOwned, V, KRef> FromIterator<(KOwned, V)> for PhShardedMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn from_iter<I>(kvs: I) -> Self
+    where
+        I: IntoIterator<Item = (KOwned, V)>,
+    {
+        let mut map = Self::default();
+        map.extend(kvs);
+        map
+    }
+}
+
+impl<KOwned, V, KRef> fmt::Debug for PhShardedMap<KOwned, V, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef> + fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,138 @@
+use std::hash::Hash;
+
+use dyn_size_of::GetSize;
+use ph::BuildSeededHasher;
+
+use crate::{Function, KeyHasher, Profile, build_function};
+
+/// A function from keys to slots, built on a PHast function. PHast leaves holes in its
+/// output range, which a sparse layout keeps. A minimal layout moves the keys the function
+/// places past the last key into those holes, so that there is exactly one slot per key, at
+/// the cost of an extra lookup for the moved keys.
+pub(crate) struct SlotFunction<H = KeyHasher> {
+    function: Function<H>,
+    /// For a minimal layout, the slot of each of the function's outputs from `num_slots` on.
+    /// Outputs which no key was given are mapped to `num_slots`, which isn't a slot.
+    moved: Box<[u32]>,
+    num_slots: usize,
+}
+
+impl<H> SlotFunction<H>
+where
+    H: BuildSeededHasher,
+{
+    /// Uses the function's outputs as slots, without moving any.
+    pub(crate) fn sparse(function: Function<H>) -> Self {
+        Self {
+            function,
+            moved: Box::default(),
+            num_slots: usize::MAX,
+        }
+    }
+
+    /// Builds a function over `keys`, returning it along with the slot of every key, in the
+    /// order the keys were given. The slots of keys which couldn't be placed are `None`.
+    pub(crate) fn build<'k, K, I>(
+        keys: I,
+        hashes: &[u64],
+        profile: Profile,
+    ) -> (Self, Vec<Option<usize>>)
+    where
+        K: ?Sized + Hash + 'k,
+        I: Iterator<Item = &'k K> + Clone,
+        H: Default,
+    {
+        let function: Function<H> = build_function(keys.clone().collect(), profile);
+        let mut indices = keys
+            .zip(hashes)
+            .map(|(key, hash)| function.get_with_top_level_hash(key, *hash))
+            .collect::<Vec<_>>();
+
+        let num_keys = indices.len();
+        let max_slot = indices.iter().flatten().max().copied();
+        if !profile.is_minimal() || max_slot.is_none_or(|max| max < num_keys) {
+            return (Self::sparse(function), indices);
+        }
+
+        let mut used = vec![false; num_keys];
+        for &idx in indices.iter().flatten() {
+            if let Some(used) = used.get_mut(idx) {
+                *used = true;
+            }
+        }
+        let mut holes = used
+            .iter()
+            .enumerate()
+            .filter(|(_, used)| !**used)
+            .map(|(idx, _)| idx as u32);
+
+        // Keys with the same output share a slot either way, so each output only needs one
+        // hole, and there are at least as many holes as outputs past the last key.
+        let num_moved = max_slot.map_or(0, |max| max + 1 - num_keys);
+        let mut moved = vec![num_keys as u32; num_moved].into_boxed_slice();
+        for idx in indices.iter_mut().flatten().filter(|idx| **idx >= num_keys) {
+            let slot = &mut moved[*idx - num_keys];
+            if *slot == num_keys as u32 {
+                *slot = holes.next().expect("fewer holes than moved keys");
+            }
+
+            *idx = *slot as usize;
+        }
+
+        let slots = Self {
+            function,
+            moved,
+            num_slots: num_keys,
+        };
+        (slots, indices)
+    }
+
+    #[inline]
+    fn slot(&self, idx: usize) -> usize {
+        match idx.checked_sub(self.num_slots) {
+            None => idx,
+            Some(moved) => self.moved.get(moved).map_or(idx, |&slot| slot as usize),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get<K>(&self, key: &K) -> Option<usize>
+    where
+        K: ?Sized + Hash,
+    {
+        Some(self.slot(self.function.get(key)?))
+    }
+
+    #[inline]
+    pub(crate) fn get_with_top_level_hash<K>(&self, key: &K, hash: u64) -> Option<usize>
+    where
+        K: ?Sized + Hash,
+    {
+        Some(self.slot(self.function.get_with_top_level_hash(key, hash)?))
+    }
+
+    pub(crate) fn hasher(&self) -> &H {
+        self.function.hasher()
+    }
+
+    /// Takes the value of `key` out of `vals`.
+    ///
+    /// # Safety
+    /// This function must have been built with `key` as one of its keys, and `vals` must have
+    /// a slot for every key.
+    pub(crate) unsafe fn take_unchecked<K, V>(&self, vals: &mut [Option<V>], key: &K) -> V
+    where
+        K: ?Sized + Hash,
+    {
+        let idx = unsafe { self.get(key).unwrap_unchecked() };
+        unsafe { vals.get_unchecked_mut(idx).take().unwrap_unchecked() }
+    }
+}
+
+impl<H> GetSize for SlotFunction<H> {
+    fn size_bytes_dyn(&self) -> usize {
+        self.function.size_bytes_dyn() + self.moved.len() * size_of::<u32>()
+    }
+
+    const USES_DYN_MEM: bool = true;
+}
@@ -0,0 +1,26 @@
+/// Allocations made by a [`PhMap`](crate::PhMap) while building its slot storage, returned by
+/// [`PhMap::build_stats`](crate::PhMap::build_stats). Allocations made internally by the
+/// perfect hash function's construction are not included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuildStats {
+    pub allocations: usize,
+    pub reallocations: usize,
+    pub bytes_allocated: usize,
+}
+
+impl BuildStats {
+    /// Records a buffer of `T` changing capacity from `old_capacity` to `new_capacity`.
+    pub(crate) fn record<T>(&mut self, old_capacity: usize, new_capacity: usize) {
+        if old_capacity == new_capacity || size_of::<T>() == 0 {
+            return;
+        }
+
+        if old_capacity == 0 {
+            self.allocations += 1;
+        } else if new_capacity != 0 {
+            self.reallocations += 1;
+        }
+
+        self.bytes_allocated += new_capacity.saturating_sub(old_capacity) * size_of::<T>();
+    }
+}
@@ -0,0 +1,65 @@
+use std::{borrow::Cow, fmt};
+
+use crate::PhSet;
+
+/// A set of strings, for keyword and reserved-word tables. This is a [`PhSet`] whose keys can
+/// be borrowed or owned, so there's no value storage at all: each slot only has the hash used
+/// to reject strings which aren't in the set.
+#[derive(Clone, Default)]
+pub struct PhStrSet {
+    inner: PhSet<Cow<'static, str>, str>,
+}
+
+impl PhStrSet {
+    #[inline]
+    pub fn contains<K>(&self, key: &K) -> bool
+    where
+        K: ?Sized + AsRef<str>,
+    {
+        self.inner.contains(key.as_ref())
+    }
+
+    /// Adds `key`, returning `false` without rebuilding if it was already in the set.
+    pub fn insert(&mut self, key: String) -> bool {
+        self.inner.insert(Cow::Owned(key))
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Iterates over the strings in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.inner.iter().map(|key| &**key)
+    }
+}
+
+/// Strings which are already in the set, or appear more than once, are only added once.
+impl Extend<String> for PhStrSet {
+    fn extend<I>(&mut self, keys: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.inner.extend(keys.into_iter().map(Cow::Owned));
+    }
+}
+
+/// Borrows the strings rather than copying them.
+impl Extend<&'static str> for PhStrSet {
+    fn extend<I>(&mut self, keys: I)
+    where
+        I: IntoIterator<Item = &'static str>,
+    {
+        self.inner.extend(keys.into_iter().map(Cow::Borrowed));
+    }
+}
+
+impl fmt::Debug for PhStrSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
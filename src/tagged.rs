@@ -0,0 +1,122 @@
+use std::hash::Hash;
+
+use crate::PhMap;
+
+/// A [`PhMap`] with a small tag stored alongside each entry. Tags are kept in their own dense
+/// array indexed by slot, so they don't increase the size of `V`.
+pub struct PhTaggedMap<KOwned, V, T, KRef = KOwned>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    inner: PhMap<KOwned, V, KRef>,
+    tags: Vec<T>,
+}
+
+impl<KOwned, V, T, KRef> Default for PhTaggedMap<KOwned, V, T, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+{
+    fn default() -> Self {
+        Self {
+            inner: Default::default(),
+            tags: vec![],
+        }
+    }
+}
+
+impl<KOwned, V, T, KRef> PhTaggedMap<KOwned, V, T, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    T: Copy + Default,
+{
+    /// Inserts the entry, replacing the value and tag if `key` is already in the map. The map
+    /// is only rebuilt if `key` is new.
+    pub fn insert(&mut self, key: KOwned, value: V, tag: T) {
+        if let Some(idx) = self.inner.find_index(key.as_ref()) {
+            self.inner.values[idx] = Some(value);
+            self.tags[idx] = tag;
+            return;
+        }
+
+        self.extend(std::iter::once((key, value, tag)))
+    }
+
+    pub fn get<K>(&self, key: &K) -> Option<&V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.inner.get(key)
+    }
+
+    pub fn get_mut<K>(&mut self, key: &K) -> Option<&mut V>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        self.inner.get_mut(key)
+    }
+
+    pub fn get_with_tag<K>(&self, key: &K) -> Option<(&V, T)>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = self.inner.find_index(key.as_ref())?;
+        let value = self.inner.values[idx].as_ref()?;
+
+        Some((value, self.tags[idx]))
+    }
+
+    pub fn tag<K>(&self, key: &K) -> Option<T>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = self.inner.find_index(key.as_ref())?;
+
+        Some(self.tags[idx])
+    }
+
+    /// Sets the tag for `key`, returning the previous tag or `None` if `key` is not in the map.
+    pub fn set_tag<K>(&mut self, key: &K, tag: T) -> Option<T>
+    where
+        K: ?Sized + AsRef<KRef>,
+    {
+        let idx = self.inner.find_index(key.as_ref())?;
+
+        Some(std::mem::replace(&mut self.tags[idx], tag))
+    }
+}
+
+impl<KOwned, V, T, KRef> Extend<(KOwned, V, T)> for PhTaggedMap<KOwned, V, T, KRef>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    T: Copy + Default,
+{
+    fn extend<I>(&mut self, entries: I)
+    where
+        I: IntoIterator<Item = (KOwned, V, T)>,
+    {
+        // Rebuilding keeps existing keys in order and appends the new ones, so collect the
+        // tags in key order and then scatter them back out to the new slots.
+        let mut tags_in_key_order = self
+            .inner
+            .iter_indexed()
+            .map(|(idx, _, _)| self.tags[idx])
+            .collect::<Vec<_>>();
+
+        self.inner
+            .extend(entries.into_iter().map(|(key, value, tag)| {
+                tags_in_key_order.push(tag);
+                (key, value)
+            }));
+
+        self.tags.clear();
+        self.tags.resize(self.inner.values.len(), T::default());
+
+        for ((idx, _, _), tag) in self.inner.iter_indexed().zip(tags_in_key_order) {
+            self.tags[idx] = tag;
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::hash::Hash;
+
+use ph::BuildSeededHasher;
+
+use crate::{IntegrityError, PhMap};
+
+impl<KOwned, V, KRef, H> PhMap<KOwned, V, KRef, H>
+where
+    KRef: ?Sized + Hash,
+    KOwned: AsRef<KRef>,
+    H: BuildSeededHasher + Default,
+{
+    /// Checks that every stored key resolves through the perfect hash function to an occupied
+    /// slot with a matching hash, catching corruption before it shows up as wrong answers.
+    pub fn verify(&self) -> Result<(), IntegrityError> {
+        self.verify_sample(1)
+    }
+
+    /// Like [`PhMap::verify`], but only probes every `every`th key.
+    pub fn verify_sample(&self, every: usize) -> Result<(), IntegrityError> {
+        if self.values.len() != self.top_level_hashes.len() {
+            return Err(IntegrityError::LengthMismatch {
+                values: self.values.len(),
+                hashes: self.top_level_hashes.len(),
+            });
+        }
+
+        let hasher = self.to_index.hasher();
+
+        for (key_index, key) in self.keys.iter().enumerate().step_by(every.max(1)) {
+            let hash = hasher.hash_one(key.as_ref(), 0);
+            let slot = self
+                .to_index
+                .get_with_top_level_hash(key.as_ref(), hash)
+                .filter(|slot| *slot < self.values.len())
+                .ok_or(IntegrityError::MissingSlot { key_index })?;
+
+            if self.values[slot].is_none() || !self.top_level_hashes.matches(slot, hash) {
+                return Err(IntegrityError::SlotMismatch { key_index, slot });
+            }
+        }
+
+        Ok(())
+    }
+}